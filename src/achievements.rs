@@ -0,0 +1,164 @@
+use std::{ collections::{ BTreeMap, BTreeSet }, path::PathBuf };
+use chrono::{ DateTime, Utc, Duration, Timelike };
+use chrono_tz::Tz;
+use json::JsonValue;
+use crate::{ config::Target, event::Event, template::Message, error::{ FestiveResult, FestiveError }};
+
+// consecutive puzzles which must be completed within an hour of unlocking
+const LIGHTNING_DAYS : u32 = 3;
+
+// puzzles which must be completed within a single day
+const CATCH_UP_DAYS : usize = 5;
+
+// puzzles completed after midnight, and before this hour, are completed in the night
+const NIGHT_END : u32 = 5;
+
+// milestones reached by a member during a year, each announced once when earned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Achievement
+{
+    FirstGold,
+    AllStars,
+    Lightning,
+    NightOwl,
+    CatchUp
+}
+
+impl Achievement
+{
+    fn iter() -> impl Iterator<Item = Achievement>
+    {
+        [Achievement::FirstGold, Achievement::AllStars, Achievement::Lightning, Achievement::NightOwl, Achievement::CatchUp].into_iter()
+    }
+
+    // key of this achievement in the cached file
+    fn key(self) -> &'static str
+    {
+        match self
+        {
+            Achievement::FirstGold => "first_gold",
+            Achievement::AllStars  => "all_stars",
+            Achievement::Lightning => "lightning",
+            Achievement::NightOwl  => "night_owl",
+            Achievement::CatchUp   => "catch_up"
+        }
+    }
+
+    // achievements earned by each member over a year's events, keyed by their numeric IDs
+    // a puzzle is completed when its second star is, and night-time is in the given time zone
+    pub fn earned(year : i32, events : &[Event], timezone : Tz) -> FestiveResult<BTreeMap<u64, BTreeSet<Achievement>>>
+    {
+        let mut completed : BTreeMap<u64, BTreeMap<u32, DateTime<Utc>>> = BTreeMap::new();
+        for e in events.iter().filter(|e| e.star() == 2)
+        {
+            completed.entry(e.member()).or_default().insert(e.day(), *e.timestamp());
+        }
+
+        let mut earned = BTreeMap::new();
+        for (member, days) in completed
+        {
+            let mut achievements = BTreeSet::from([Achievement::FirstGold]);
+            if days.len() == 25 { achievements.insert(Achievement::AllStars); }
+
+            // runs of consecutive puzzles completed within an hour of unlocking
+            let mut run = 0;
+            for day in 1 ..= 25
+            {
+                let quick = match days.get(&day) { Some(ts) => *ts - Event::puzzle_unlock(year, day)? <= Duration::hours(1), None => false };
+                run = if quick { run + 1 } else { 0 };
+                if run == LIGHTNING_DAYS { achievements.insert(Achievement::Lightning); }
+            }
+
+            if days.values().any(|ts| ts.with_timezone(&timezone).hour() < NIGHT_END) { achievements.insert(Achievement::NightOwl); }
+
+            let mut timestamps = days.values().collect::<Vec<_>>();
+            timestamps.sort_unstable();
+            if timestamps.windows(CATCH_UP_DAYS).any(|w| *w[CATCH_UP_DAYS - 1] - *w[0] < Duration::days(1)) { achievements.insert(Achievement::CatchUp); }
+
+            earned.insert(member, achievements);
+        }
+        Ok(earned)
+    }
+
+    pub fn fmt(self, year : i32, name : &str, target : &Target) -> String
+    {
+        target.render(Message::Achievement, &[("year", &year), ("name", &name), ("achievement", &target.locale.achievement(self))])
+    }
+
+    // every member's achievements for the year, in order of their names
+    pub fn recap(year : i32, earned : &BTreeMap<u64, BTreeSet<Achievement>>, events : &[Event], target : &Target) -> String
+    {
+        let mut lines = earned.iter()
+                              .filter_map(|(member, achievements)|
+                              {
+                                  let name = events.iter().find(|e| e.member() == *member)?.name(false);
+                                  let list = achievements.iter().map(|a| target.locale.achievement(*a)).collect::<Vec<_>>().join(", ");
+                                  Some((name.clone(), target.render(Message::AchievementsLine, &[("name", &name), ("achievements", &list)])))
+                              })
+                              .collect::<Vec<_>>();
+        lines.sort_unstable();
+        std::iter::once(target.render(Message::AchievementsRecap, &[("year", &year)])).chain(lines.into_iter().map(|(_, line)| line)).collect::<Vec<_>>().join("\n")
+    }
+}
+
+// per-year, per-leaderboard record of members' announced achievements, cached to the filesystem
+fn path(year : i32, leaderboard : &str) -> PathBuf
+{
+    PathBuf::from(format!("achievements_{year}_{leaderboard}.json"))
+}
+
+// a missing or malformed record is treated as no previous record
+pub fn read(year : i32, leaderboard : &str) -> Option<BTreeMap<u64, BTreeSet<Achievement>>>
+{
+    let contents = std::fs::read_to_string(path(year, leaderboard)).ok()?;
+    json::parse(&contents).ok()?
+        .entries()
+        .map(|(id, keys)| Some((id.parse().ok()?, keys.members().map(|key| Achievement::iter().find(|a| key.as_str() == Some(a.key()))).collect::<Option<_>>()?)))
+        .collect()
+}
+
+pub fn write(year : i32, leaderboard : &str, earned : &BTreeMap<u64, BTreeSet<Achievement>>) -> FestiveResult<()>
+{
+    let mut json = JsonValue::new_object();
+    for (id, achievements) in earned { json[id.to_string()] = achievements.iter().map(|a| a.key()).collect::<Vec<_>>().into() }
+    std::fs::write(path(year, leaderboard), json.dump()).map_err(|_| FestiveError::File)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::event::tests::{ at, events };
+
+    // both stars of a puzzle, completing it at a timestamp
+    fn gold(day : u32, ts : i64) -> [(u32, u8, i64) ; 2]
+    {
+        [(day, 1, ts - 60), (day, 2, ts)]
+    }
+
+    #[test]
+    fn achievements_are_earned_by_completing_puzzles()
+    {
+        let lightning = (1 ..= 3).flat_map(|day| gold(day, at(day, 1800))).collect::<Vec<_>>();
+        let broken    = [gold(1, at(1, 1800)), gold(2, at(2, 1800)), gold(3, at(3, 7200)), gold(4, at(4, 1800))].concat();
+        let catch_up  = (1 ..= 5).flat_map(|day| gold(day, at(5, 3600 * day as i64))).collect::<Vec<_>>();
+        let all_stars = (1 ..= 25).flat_map(|day| gold(day, at(day, 7200))).collect::<Vec<_>>();
+        let events    = events(&[(1, Some("Alice"), &lightning), (2, Some("Bob"), &broken), (3, Some("Carol"), &catch_up), (4, Some("Dave"), &all_stars), (5, Some("Eve"), &[(1, 1, at(1, 60))])]);
+
+        let earned = Achievement::earned(2025, &events, Tz::UTC).unwrap();
+        assert_eq!(earned[&1], BTreeSet::from([Achievement::FirstGold, Achievement::Lightning]));
+        assert_eq!(earned[&2], BTreeSet::from([Achievement::FirstGold]));
+        assert_eq!(earned[&3], BTreeSet::from([Achievement::FirstGold, Achievement::CatchUp]));
+        assert_eq!(earned[&4], BTreeSet::from([Achievement::FirstGold, Achievement::AllStars]));
+        assert!(!earned.contains_key(&5));
+    }
+
+    #[test]
+    fn night_time_is_in_the_given_time_zone()
+    {
+        // puzzles unlock at midnight in New York, which isn't night-time in UTC
+        let events = events(&[(1, Some("Alice"), &gold(1, at(1, 1800)))]);
+        assert!(!Achievement::earned(2025, &events, Tz::UTC).unwrap()[&1].contains(&Achievement::NightOwl));
+        assert!( Achievement::earned(2025, &events, chrono_tz::America::New_York).unwrap()[&1].contains(&Achievement::NightOwl));
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+use chrono::{ DateTime, Utc, Duration };
+use json::{ object, JsonValue };
+use crate::{ event::Event, error::{ FestiveResult, FestiveError }};
+
+// AoC API response cached on disk, persisting across restarts
+// the fetched timestamp is the time at which the request for the response was last sent, as the request limit applies to actual requests
+pub struct Cache
+{
+    pub fetched:       DateTime<Utc>,
+    pub etag:          Option<String>,
+    pub last_modified: Option<String>,
+    pub body:          String
+}
+
+impl Cache
+{
+    fn path(year : i32, leaderboard : &str) -> PathBuf
+    {
+        PathBuf::from(format!("response_{year}_{leaderboard}.json"))
+    }
+
+    // how long to wait before sending a request at a given time, or None if the cached response is used instead
+    // requests are sent no more often than the request limit, regardless of the refresh duration
+    // iterations begin on schedule, but requests are sent slightly later, so requests due within a minute are waited for rather than skipped
+    pub fn wait(&self, now : &DateTime<Utc>, refresh : Duration) -> Option<Duration>
+    {
+        let remaining = self.fetched + refresh.max(Event::request_limit()) - *now;
+        (remaining < Duration::minutes(1)).then(|| remaining.max(Duration::zero()))
+    }
+
+    // a missing or malformed cache file is treated as no cached response
+    pub fn read(year : i32, leaderboard : &str) -> Option<Cache>
+    {
+        let contents = std::fs::read_to_string(Self::path(year, leaderboard)).ok()?;
+        let json     = json::parse(&contents).ok()?;
+
+        Some(Cache
+        {
+            fetched:       DateTime::parse_from_rfc3339(json["fetched"].as_str()?).ok()?.with_timezone(&Utc),
+            etag:          json["etag"].as_str().map(str::to_string),
+            last_modified: json["last_modified"].as_str().map(str::to_string),
+            body:          json["body"].as_str()?.to_string()
+        })
+    }
+
+    pub fn write(&self, year : i32, leaderboard : &str) -> FestiveResult<()>
+    {
+        let json = object!
+        {
+            fetched:       self.fetched.to_rfc3339(),
+            etag:          self.etag.as_deref().map_or(JsonValue::Null, JsonValue::from),
+            last_modified: self.last_modified.as_deref().map_or(JsonValue::Null, JsonValue::from),
+            body:          self.body.as_str()
+        };
+
+        std::fs::write(Self::path(year, leaderboard), json.dump()).map_err(|_| FestiveError::File)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h : u32, m : u32, s : u32) -> DateTime<Utc>
+    {
+        Utc.with_ymd_and_hms(2025, 12, 1, h, m, s).unwrap()
+    }
+
+    fn fetched_at(fetched : DateTime<Utc>) -> Cache
+    {
+        Cache { fetched, etag: None, last_modified: None, body: String::new() }
+    }
+
+    #[test]
+    fn request_limit_is_measured_from_the_actual_request()
+    {
+        // an iteration scheduled for 10:15 which overran, fetching at 10:29, mustn't allow another request at 10:30
+        let cache = fetched_at(at(10, 29, 0));
+        assert_eq!(cache.wait(&at(10, 30, 0),  Duration::zero()), None);
+        assert_eq!(cache.wait(&at(10, 43, 59), Duration::zero()), Some(Duration::seconds(1)));
+        assert_eq!(cache.wait(&at(10, 44, 0),  Duration::zero()), Some(Duration::zero()));
+    }
+
+    #[test]
+    fn requests_sent_after_the_iteration_began_dont_skip_the_next_iteration()
+    {
+        // the request of an iteration beginning at 10:00 was sent a few seconds later, and the iteration at 10:15 waits for the request limit
+        let cache = fetched_at(at(10, 0, 3));
+        assert_eq!(cache.wait(&at(10, 15, 0), Duration::zero()), Some(Duration::seconds(3)));
+        assert_eq!(cache.wait(&at(10, 16, 0), Duration::zero()), Some(Duration::zero()));
+    }
+
+    #[test]
+    fn refresh_extends_the_request_limit()
+    {
+        let cache = fetched_at(at(10, 0, 0));
+        assert_eq!(cache.wait(&at(10, 15, 0), Duration::hours(1)), None);
+        assert_eq!(cache.wait(&at(11, 0, 0), Duration::hours(1)), Some(Duration::zero()));
+    }
+}
@@ -0,0 +1,100 @@
+use std::{ collections::HashMap, fmt::Write };
+use chrono::{ DateTime, Utc };
+use num_traits::ToPrimitive;
+use crate::{ config::Target, event::Event, html::escape, error::{ FestiveResult, FestiveError }};
+
+// dimensions of the plot area and the margins around it, in pixels
+// the right margin contains the legend
+const WIDTH  : f64 = 750.0;
+const HEIGHT : f64 = 400.0;
+const LEFT   : f64 = 60.0;
+const RIGHT  : f64 = 240.0;
+const TOP    : f64 = 50.0;
+const BOTTOM : f64 = 40.0;
+
+// colours of each member's line, reused when there are more members than colours
+const COLOURS : [&str ; 10] = ["#ffff66", "#00cc00", "#ff6666", "#66ccff", "#ff9933", "#cc66ff", "#ffffff", "#ff66cc", "#99cc99", "#9999cc"];
+
+// a line chart of each ranked participant's cumulative score by the end of each day, in order of the standings
+// lines end at the current day, and the final day includes every star, including those completed after the end of the 25th
+pub fn scores(year : i32, events : &[Event], current : &DateTime<Utc>, target : &Target) -> FestiveResult<String>
+{
+    let ranking = Event::ranking(events, &target.tie_breakers, current)?.into_iter().filter(|s| !s.spectator).collect::<Vec<_>>();
+
+    // cumulative score of each member at the end of each day
+    let mut ends = Vec::new();
+    for day in 2 ..= 25 { ends.push(Event::puzzle_unlock(year, day)?) }
+    let days = (1 ..= 25).map(|day| Event::puzzle_unlock(year, day)).collect::<FestiveResult<Vec<_>>>()?.into_iter().filter(|unlock| unlock <= current).count();
+    let mut totals : HashMap<u64, [f64 ; 25]> = HashMap::new();
+    for e in events
+    {
+        let score  = e.score()?.to_f64().ok_or(FestiveError::Conv)?;
+        let first  = ends.iter().take_while(|end| *end <= e.timestamp()).count();
+        let totals = totals.entry(e.member()).or_insert([0.0 ; 25]);
+        for total in &mut totals[first ..] { *total += score }
+    }
+
+    // gridlines are placed at the first step giving at most ten of them
+    let max  = totals.values().map(|t| t[24]).fold(0.0, f64::max);
+    let step = [0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0].into_iter().find(|step| max / step <= 10.0).unwrap_or(200.0);
+    let top  = (max / step).ceil().max(1.0) * step;
+    let x    = |day : usize| LEFT + WIDTH * (day - 1) as f64 / 24.0;
+    let y    = |score : f64| TOP + HEIGHT * (1.0 - score / top);
+
+    // the image is extended downwards when the legend is taller than the plot area
+    let mut svg = String::new();
+    writeln!(&mut svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"12\">", LEFT + WIDTH + RIGHT, (TOP + HEIGHT + BOTTOM).max(TOP + 16.0 * ranking.len() as f64 + BOTTOM))
+        .map_err(|_| FestiveError::Conv)?;
+    writeln!(&mut svg, "<rect width=\"100%\" height=\"100%\" fill=\"#0f0f23\"/>\n<text x=\"{LEFT}\" y=\"{}\" fill=\"#cccccc\" font-size=\"16\">Advent of Code {year}</text>", TOP / 2.0)
+        .map_err(|_| FestiveError::Conv)?;
+
+    // horizontal gridlines labelled with scores, and days along the x-axis
+    let places = if step < 1.0 { 2 } else { 0 };
+    for ix in 0 ..= (top / step).round() as usize
+    {
+        let score = ix as f64 * step;
+        writeln!(&mut svg, "<line x1=\"{LEFT}\" x2=\"{}\" y1=\"{y:.1}\" y2=\"{y:.1}\" stroke=\"#333340\"/>\n<text x=\"{}\" y=\"{:.1}\" fill=\"#cccccc\" text-anchor=\"end\">{}</text>",
+                         LEFT + WIDTH, LEFT - 8.0, y(score) + 4.0, target.locale.decimal(score, places), y = y(score)).map_err(|_| FestiveError::Conv)?;
+    }
+    for day in 1 ..= 25
+    {
+        writeln!(&mut svg, "<text x=\"{:.1}\" y=\"{}\" fill=\"#cccccc\" text-anchor=\"middle\">{day}</text>", x(day), TOP + HEIGHT + 20.0).map_err(|_| FestiveError::Conv)?;
+    }
+
+    // one line per member, with its entry in the legend
+    for (ix, standing) in ranking.iter().enumerate()
+    {
+        let colour = COLOURS[ix % COLOURS.len()];
+        let points = totals.get(&standing.member)
+                           .map(|t| t[.. days].iter().enumerate().map(|(d, &s)| format!("{:.1},{:.1}", x(d + 1), y(s))).collect::<Vec<_>>().join(" "))
+                           .unwrap_or_default();
+        let legend = TOP + 16.0 * ix as f64;
+        writeln!(&mut svg, "<polyline points=\"{points}\" fill=\"none\" stroke=\"{colour}\" stroke-width=\"2\"/>\n<rect x=\"{}\" y=\"{legend}\" width=\"10\" height=\"10\" fill=\"{colour}\"/>\n<text x=\"{}\" y=\"{}\" fill=\"#cccccc\">{}</text>",
+                         LEFT + WIDTH + 20.0, LEFT + WIDTH + 36.0, legend + 10.0, escape(standing.name)).map_err(|_| FestiveError::Conv)?;
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::event::tests::{ at, events };
+
+    fn points(svg : &str) -> Vec<usize>
+    {
+        svg.lines().filter_map(|line| line.strip_prefix("<polyline points=\"")).map(|line| line.split('"').next().unwrap().split_whitespace().count()).collect()
+    }
+
+    #[test]
+    fn lines_end_at_the_current_day()
+    {
+        let events = events(&[(1, Some("Alice"), &[(1, 1, at(1, 60)), (2, 1, at(2, 60))]), (2, Some("Bob"), &[(1, 1, at(1, 300))])]);
+        let chart  = |current| scores(2025, &events, &DateTime::from_timestamp(current, 0).unwrap(), &Target::from_json("{}")).unwrap();
+        assert_eq!(points(&chart(at(3, 0) - 1)), [2, 2]);
+        assert_eq!(points(&chart(at(3, 0))),     [3, 3]);
+        assert_eq!(points(&chart(at(31, 0))),    [25, 25]);
+    }
+}
@@ -0,0 +1,750 @@
+use std::{ collections::{ BTreeMap, HashMap }, path::{ Path, PathBuf }, process::Command, time::SystemTime };
+use json::JsonValue;
+use chrono::{ DateTime, Utc, NaiveTime, TimeZone, Duration };
+use chrono_tz::Tz;
+use crate::{ env::Var, event::TieBreaker, export::{ Export, Inline, Layout }, webhook::{ Webhook, Route, Overflow, Format }, locale::Locale, template::{ Message, Templates }, error::{ FestiveResult, FestiveError }};
+
+// configuration that may be changed while Festive Bot is running
+// values are read from environment variables, falling back to the optional configuration file
+pub struct Config
+{
+    pub leaderboard: String,
+    pub session:     String,
+    pub targets:     BTreeMap<String, Target>,
+    routes:          HashMap<Route, Vec<String>>,
+    pub members:     BTreeMap<u64, Member>,
+    pub mentions:    bool,
+    pub aggregate:   Aggregate,
+    pub overflow:    Overflow,
+    pub output:      Option<Output>,
+    watched:         Vec<(PathBuf, Option<SystemTime>)>
+}
+
+// where and how messages are delivered to a notification target
+// the locale, time zone, and templates default to the top-level configuration
+pub struct Target
+{
+    pub url:          Option<String>,
+    pub format:       Format,
+    pub locale:       Locale,
+    pub timezone:     Tz,
+    pub templates:    Templates,
+    pub quiet:        Option<Quiet>,
+    pub standings:    Vec<Export>,
+    pub inline:       Option<Inline>,
+    pub tie_breakers: Vec<TieBreaker>
+}
+
+// times of day, in a target's time zone, during which messages to it are held back
+// unlock announcements may be exempt, being sent regardless
+pub struct Quiet
+{
+    pub start:          NaiveTime,
+    pub end:            NaiveTime,
+    pub exempt_unlocks: bool
+}
+
+impl Target
+{
+    pub fn render(&self, message : Message, values : &[(&str, &dyn std::fmt::Display)]) -> String
+    {
+        self.templates.render(self.locale, message, values)
+    }
+
+    // timestamps are shown in this target's time zone and language
+    pub fn timestamp(&self, ts : &DateTime<Utc>) -> String
+    {
+        self.locale.timestamp(&ts.with_timezone(&self.timezone))
+    }
+
+    // when the quiet hours that ts falls within end, if it falls within any
+    // quiet hours may span midnight, with the end time earlier than the start time
+    pub fn quiet_until(&self, ts : &DateTime<Utc>) -> Option<DateTime<Utc>>
+    {
+        let quiet = self.quiet.as_ref()?;
+        let local = ts.with_timezone(&self.timezone);
+        let time  = local.time();
+        let quiet_now = if quiet.start < quiet.end { quiet.start <= time && time < quiet.end }
+                        else                       { quiet.start <= time || time < quiet.end };
+        if !quiet_now { return None }
+
+        // an end time skipped by a daylight saving transition is taken to be an hour later
+        let date = if time < quiet.end { local.date_naive() } else { local.date_naive().succ_opt()? };
+        let end  = date.and_time(quiet.end);
+        self.timezone.from_local_datetime(&end).earliest()
+            .or_else(|| self.timezone.from_local_datetime(&(end + Duration::hours(1))).earliest())
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+}
+
+// a directory which standings are written to each iteration, in each of the formats
+pub struct Output
+{
+    pub directory: PathBuf,
+    pub formats:   Vec<Export>
+}
+
+// per-member configuration, keyed by numeric AoC member ID
+pub struct Member
+{
+    pub alias:     Option<String>,
+    pub discord:   Option<u64>,
+    pub ignore:    bool,
+    pub spectator: bool,
+    pub team:      Option<String>
+}
+
+// how members' scores are combined into their team's score
+#[derive(Clone, Copy)]
+pub enum Aggregate
+{
+    Sum,
+    Average,
+    Top(usize)
+}
+
+// places that a secret value may be read from
+enum Source
+{
+    Value(String),
+    File(PathBuf),
+    Command(String)
+}
+
+impl Source
+{
+    // surrounding whitespace is trimmed, and the value may not be empty
+    fn read(self, name : &str) -> FestiveResult<String>
+    {
+        let value = match self
+        {
+            Source::Value(value) => value,
+            Source::File(path)   =>
+            {
+                println!("reading {} from file {}", name, path.display());
+                std::fs::read_to_string(&path).map_err(|e|
+                {
+                    println!("failed to read file {}: {e}", path.display());
+                    FestiveError::Secret(name.to_string())
+                })?
+            },
+            Source::Command(cmd) =>
+            {
+                println!("reading {} from the output of command {cmd:?}", name);
+                let output = Command::new("sh").arg("-c").arg(&cmd).output().map_err(|e|
+                {
+                    println!("failed to run command {cmd:?}: {e}");
+                    FestiveError::Secret(name.to_string())
+                })?;
+                if !output.status.success()
+                {
+                    println!("command {cmd:?} failed: {}", output.status);
+                    return Err(FestiveError::Secret(name.to_string()))
+                }
+                String::from_utf8(output.stdout).map_err(|_|
+                {
+                    println!("the output of command {cmd:?} is not UTF-8 encoded");
+                    FestiveError::Secret(name.to_string())
+                })?
+            }
+        };
+
+        let value = value.trim();
+        if value.is_empty()
+        {
+            println!("the value of {} is empty", name);
+            return Err(FestiveError::Secret(name.to_string()))
+        }
+        Ok(value.to_string())
+    }
+}
+
+impl Config
+{
+    pub fn load() -> FestiveResult<Config>
+    {
+        let mut watched = Vec::new();
+
+        // the configuration file is optional, treat it as empty when unset
+        let file = match Var::Config.get().ok().map(PathBuf::from)
+        {
+            None       => JsonValue::new_object(),
+            Some(path) =>
+            {
+                println!("reading configuration file {}", path.display());
+                watched.push((path.clone(), Self::modified(&path)));
+                let contents = std::fs::read_to_string(&path).map_err(|_| FestiveError::File)?;
+                let json     = json::parse(&contents).map_err(|_| FestiveError::Parse)?;
+                if !json.is_object()
+                {
+                    println!("the configuration file should contain a JSON object");
+                    return Err(FestiveError::Parse)
+                }
+                json
+            }
+        };
+
+        // the notify and status targets always exist, with their URLs read like the other secrets
+        // further targets are named by the keys of the "targets" object, and must have URLs
+        if !file["targets"].is_null() && !file["targets"].is_object() { return Err(Self::invalid("targets", "an object")) }
+        let mut targets = BTreeMap::new();
+        targets.insert("notify".to_string(), Self::target(Self::secret(Var::Notify, &file["notify"], &mut watched)?, &file, "notify")?);
+        targets.insert("status".to_string(), Self::target(Self::secret(Var::Status, &file["status"], &mut watched)?, &file, "status")?);
+        for (name, target) in file["targets"].entries().filter(|(name, _)| !["notify", "status"].contains(name))
+        {
+            let key = format!("targets.{name}.url");
+            let url = Self::source(&target["url"], &key)?.ok_or_else(|| Self::invalid(&key, "set for targets other than \"notify\" and \"status\""))?;
+            let url = Self::read(url, &key, &mut watched)?;
+            targets.insert(name.to_string(), Self::target(Some(url), &file, name)?);
+        }
+
+        Ok(Config
+        {
+            leaderboard: Self::secret(Var::Leaderboard, &file["leaderboard"], &mut watched)?.ok_or(FestiveError::Var(Var::Leaderboard))?,
+            session:     Self::secret(Var::Session,     &file["session"],     &mut watched)?.ok_or(FestiveError::Var(Var::Session))?,
+            routes:      Self::routes(&file["routes"], &targets)?,
+            targets,
+            members:     Self::members(&file["members"])?,
+            mentions:    Self::flag(&file["mentions"], "mentions")?,
+            aggregate:   Self::aggregate(&file["teams"]["aggregate"])?,
+            overflow:    Self::overflow(&file["overflow"])?,
+            output:      Self::output(&file["output"])?,
+            watched
+        })
+    }
+
+    // determine where a secret should be read from, then read it
+    // environment variables take precedence over the configuration file
+    fn secret(var : Var, value : &JsonValue, watched : &mut Vec<(PathBuf, Option<SystemTime>)>) -> FestiveResult<Option<String>>
+    {
+        let source = match (var.get().ok(), var.get_file())
+        {
+            (Some(value), _)   => Source::Value(value),
+            (None, Some(path)) => Source::File(PathBuf::from(path)),
+            (None, None)       => match Self::source(value, var.key())?
+            {
+                Some(source) => source,
+                None         => return Ok(None)
+            }
+        };
+        Self::read(source, var.key(), watched).map(Some)
+    }
+
+    // a secret in the configuration file may be given directly as a string, or read from a file or command
+    fn source(value : &JsonValue, name : &str) -> FestiveResult<Option<Source>>
+    {
+        match value
+        {
+            JsonValue::Null    => Ok(None),
+            v if v.is_string() => Ok(Some(Source::Value(v.to_string()))),
+            v if v.is_object() => match (&v["file"], &v["command"])
+            {
+                (file, JsonValue::Null) if file.is_string()    => Ok(Some(Source::File(PathBuf::from(file.to_string())))),
+                (JsonValue::Null, cmd)  if cmd.is_string()     => Ok(Some(Source::Command(cmd.to_string()))),
+                _                                              =>
+                {
+                    println!("configuration value for {name} should contain exactly one of the string keys \"file\" or \"command\"");
+                    Err(FestiveError::Parse)
+                }
+            },
+            _ =>
+            {
+                println!("configuration value for {name} should be a string or an object");
+                Err(FestiveError::Parse)
+            }
+        }
+    }
+
+    // files are watched, so changing them causes the configuration to be reloaded
+    fn read(source : Source, name : &str, watched : &mut Vec<(PathBuf, Option<SystemTime>)>) -> FestiveResult<String>
+    {
+        if let Source::File(path) = &source { watched.push((path.clone(), Self::modified(path))) }
+        source.read(name)
+    }
+
+    // settings for a notification target, from its entry in the "targets" object, falling back to the top-level settings
+    // its templates are layered over the top-level templates
+    fn target(url : Option<String>, file : &JsonValue, name : &str) -> FestiveResult<Target>
+    {
+        let key       = format!("targets.{name}");
+        let overrides = &file["targets"][name];
+        if !overrides.is_null() && !overrides.is_object() { return Err(Self::invalid(&key, "an object")) }
+
+        // the target's own setting, if present, otherwise the top-level setting, along with its key for error messages
+        let setting = |setting : &str| if overrides[setting].is_null() { (&file[setting], setting.to_string()) } else { (&overrides[setting], format!("{key}.{setting}")) };
+
+        let (locale, locale_key) = setting("locale");
+        let locale = match Self::string(locale, &locale_key)?
+        {
+            None       => Locale::En,
+            Some(code) => Locale::parse(&code).ok_or_else(|| Self::invalid(&locale_key, "one of \"en\", \"fr\", \"de\", or \"ja\""))?
+        };
+
+        let (timezone, timezone_key) = setting("timezone");
+        let timezone = match Self::string(timezone, &timezone_key)?
+        {
+            None       => Tz::UTC,
+            Some(name) => name.parse().map_err(|_| Self::invalid(&timezone_key, "an IANA time zone name, such as \"Europe/Paris\""))?
+        };
+
+        let (quiet, quiet_key) = setting("quiet");
+        let quiet = Self::quiet(quiet, &quiet_key)?;
+
+        // the format is specific to each target
+        let format = match overrides["format"].as_str()
+        {
+            _ if overrides["format"].is_null() => Format::Discord,
+            Some("discord")                    => Format::Discord,
+            Some("json")                       => Format::Json,
+            _                                  => return Err(Self::invalid(&format!("{key}.format"), "\"discord\" or \"json\""))
+        };
+
+        // standings are attached as text by default
+        let (standings, standings_key) = setting("standings");
+        let standings = if standings.is_null() { vec![Export::Text] } else { Self::exports(standings, &standings_key)? };
+
+        let (inline, inline_key) = setting("inline");
+        let inline = Self::inline(inline, &inline_key)?;
+
+        // participants with equal scores are separated by star count by default
+        let (tie_breakers, tie_breakers_key) = setting("tie_breakers");
+        let tie_breakers = if tie_breakers.is_null() { vec![TieBreaker::Stars] } else { Self::tie_breakers(tie_breakers, &tie_breakers_key)? };
+
+        let templates = Templates::parse(&file["templates"], "templates")?.overlay(Templates::parse(&overrides["templates"], &format!("{key}.templates"))?);
+        Ok(Target { url, format, locale, timezone, templates, quiet, standings, inline, tie_breakers })
+    }
+
+    // an array of standings formats
+    fn exports(json : &JsonValue, key : &str) -> FestiveResult<Vec<Export>>
+    {
+        if !json.is_array() { return Err(Self::invalid(key, "an array of formats")) }
+        json.members()
+            .map(|format| format.as_str().and_then(Export::parse).ok_or_else(|| Self::invalid(key, "an array containing \"text\", \"csv\", \"json\", \"html\", \"png\", or \"chart\"")))
+            .collect()
+    }
+
+    // an array of tie-breakers, applied in turn
+    fn tie_breakers(json : &JsonValue, key : &str) -> FestiveResult<Vec<TieBreaker>>
+    {
+        if !json.is_array() { return Err(Self::invalid(key, "an array of tie-breakers")) }
+        json.members()
+            .map(|rule| rule.as_str().and_then(TieBreaker::parse).ok_or_else(|| Self::invalid(key, "an array containing \"most_stars\", \"earliest_final_star\", \"total_solve_time\", or \"most_gold_stars\"")))
+            .collect()
+    }
+
+    // no inline standings by default, or a layout, optionally with a number of participants to truncate to
+    fn inline(json : &JsonValue, key : &str) -> FestiveResult<Option<Inline>>
+    {
+        let (layout, top) = if json.is_object() { (&json["layout"], &json["top"]) } else { (json, &JsonValue::Null) };
+        let layout = match layout.as_str()
+        {
+            _ if json.is_null() => return Ok(None),
+            Some("markdown")    => Layout::Markdown,
+            Some("code")        => Layout::Code,
+            _                   => return Err(Self::invalid(key, "\"markdown\", \"code\", or { \"layout\": \"markdown\" or \"code\", \"top\": N }"))
+        };
+        let top = match top
+        {
+            JsonValue::Null => None,
+            value           => Some(value.as_usize().filter(|&n| 0 < n).ok_or_else(|| Self::invalid(&format!("{key}.top"), "a positive integer"))?)
+        };
+        Ok(Some(Inline { layout, top }))
+    }
+
+    // the output directory and its formats, both required when writing standings to disk
+    fn output(json : &JsonValue) -> FestiveResult<Option<Output>>
+    {
+        if json.is_null()    { return Ok(None) }
+        if !json.is_object() { return Err(Self::invalid("output", "an object")) }
+        let directory = Self::string(&json["directory"], "output.directory")?.ok_or_else(|| Self::invalid("output.directory", "a string"))?;
+        Ok(Some(Output { directory: PathBuf::from(directory), formats: Self::exports(&json["formats"], "output.formats")? }))
+    }
+
+    // the names of the targets that each route is sent to, falling back to each route's default targets
+    fn routes(json : &JsonValue, targets : &BTreeMap<String, Target>) -> FestiveResult<HashMap<Route, Vec<String>>>
+    {
+        if !json.is_null() && !json.is_object() { return Err(Self::invalid("routes", "an object")) }
+        if let Some((key, _)) = json.entries().find(|(key, _)| Route::iter().all(|r| r.key() != *key))
+        {
+            return Err(Self::invalid("routes", &format!("keyed by routes, found {key:?}, expected one of: {}", Route::iter().map(Route::key).collect::<Vec<_>>().join(", "))))
+        }
+
+        let mut routes = HashMap::new();
+        for route in Route::iter()
+        {
+            let key   = format!("routes.{}", route.key());
+            let names = match &json[route.key()]
+            {
+                JsonValue::Null     => route.default_targets().iter().map(|name| Some(name.to_string())).collect(),
+                v if v.is_array()   => v.members().map(|name| name.as_str().map(str::to_string)).collect::<Option<Vec<_>>>(),
+                _                   => None
+            };
+            let names = names.filter(|names| names.iter().all(|name| targets.contains_key(name)))
+                             .ok_or_else(|| Self::invalid(&key, &format!("an array of target names, from: {}", targets.keys().cloned().collect::<Vec<_>>().join(", "))))?;
+            routes.insert(route, names);
+        }
+        Ok(routes)
+    }
+
+    // the targets a route is sent to
+    pub fn route(&self, route : Route) -> impl Iterator<Item = Webhook<'_>>
+    {
+        self.routes.get(&route)
+                   .into_iter()
+                   .flatten()
+                   .filter_map(|name| self.targets.get_key_value(name))
+                   .map(|(name, target)| Webhook { name, target })
+    }
+
+    // quiet hours, with start and end times written as "HH:MM"
+    fn quiet(json : &JsonValue, key : &str) -> FestiveResult<Option<Quiet>>
+    {
+        if json.is_null()    { return Ok(None) }
+        if !json.is_object() { return Err(Self::invalid(key, "an object")) }
+
+        let time = |field : &str|
+        {
+            let key = format!("{key}.{field}");
+            json[field].as_str()
+                       .and_then(|s| NaiveTime::parse_from_str(s, "%H:%M").ok())
+                       .ok_or_else(|| Self::invalid(&key, "a time of day, such as \"22:00\""))
+        };
+
+        let (start, end) = (time("start")?, time("end")?);
+        if start == end { return Err(Self::invalid(&format!("{key}.end"), "different from the start time")) }
+        Ok(Some(Quiet { start, end, exempt_unlocks: Self::flag(&json["exempt_unlocks"], &format!("{key}.exempt_unlocks"))? }))
+    }
+
+    // aliases and Discord user IDs for members
+    fn members(json : &JsonValue) -> FestiveResult<BTreeMap<u64, Member>>
+    {
+        if !json.is_null() && !json.is_object() { return Err(Self::invalid("members", "an object")) }
+
+        let mut members = BTreeMap::new();
+        for (id, member) in json.entries()
+        {
+            let key     = format!("members.{id}");
+            let numeric = id.parse().map_err(|_| Self::invalid("members", "keyed by numeric AoC member IDs"))?;
+            if !member.is_object() { return Err(Self::invalid(&key, "an object")) }
+
+            members.insert(numeric, Member
+            {
+                alias:     Self::string(&member["alias"],   &format!("{key}.alias"))?,
+                ignore:    Self::flag(&member["ignore"],      &format!("{key}.ignore"))?,
+                spectator: Self::flag(&member["spectator"], &format!("{key}.spectator"))?,
+                team:      Self::string(&member["team"],    &format!("{key}.team"))?,
+
+                // Discord user IDs may exceed the precision of JSON numbers, so strings are also accepted
+                discord:   match &member["discord"]
+                {
+                    JsonValue::Null => None,
+                    value           => Some(value.as_u64().or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+                                                          .ok_or_else(|| Self::invalid(&format!("{key}.discord"), "a Discord user ID"))?)
+                }
+            });
+        }
+        Ok(members)
+    }
+
+    // sum by default, or average, or the sum of the top N members' scores
+    fn aggregate(json : &JsonValue) -> FestiveResult<Aggregate>
+    {
+        match (json.as_str(), json["top"].as_usize())
+        {
+            _ if json.is_null()      => Ok(Aggregate::Sum),
+            (Some("sum"),     _)     => Ok(Aggregate::Sum),
+            (Some("average"), _)     => Ok(Aggregate::Average),
+            (None, Some(n)) if 0 < n => Ok(Aggregate::Top(n)),
+            _                        => Err(Self::invalid("teams.aggregate", "\"sum\", \"average\", or { \"top\": N }"))
+        }
+    }
+
+    // split overflowing messages by default, or attach the overflow
+    fn overflow(json : &JsonValue) -> FestiveResult<Overflow>
+    {
+        match json.as_str()
+        {
+            _ if json.is_null() => Ok(Overflow::Split),
+            Some("split")       => Ok(Overflow::Split),
+            Some("attach")      => Ok(Overflow::Attach),
+            _                   => Err(Self::invalid("overflow", "\"split\" or \"attach\""))
+        }
+    }
+
+    // optional string value
+    fn string(json : &JsonValue, key : &str) -> FestiveResult<Option<String>>
+    {
+        match json
+        {
+            JsonValue::Null => Ok(None),
+            value           => value.as_str().map(|s| Some(s.to_string())).ok_or_else(|| Self::invalid(key, "a string"))
+        }
+    }
+
+    // optional boolean value, defaulting to false
+    fn flag(json : &JsonValue, key : &str) -> FestiveResult<bool>
+    {
+        match json
+        {
+            JsonValue::Null => Ok(false),
+            value           => value.as_bool().ok_or_else(|| Self::invalid(key, "a boolean"))
+        }
+    }
+
+    // print an error message about an unexpected configuration value
+    fn invalid(key : &str, expected : &str) -> FestiveError
+    {
+        println!("configuration key {key:?} should be {expected}");
+        FestiveError::Parse
+    }
+
+    // whether any file the configuration was read from has been modified since it was read
+    pub fn changed(&self) -> bool
+    {
+        self.watched.iter().any(|(path, modified)| Self::modified(path) != *modified)
+    }
+
+    // record the current modification times, so a failed reload isn't retried until the files change again
+    pub fn mark_unchanged(&mut self)
+    {
+        for (path, modified) in self.watched.iter_mut() { *modified = Self::modified(path) }
+    }
+
+    fn modified(path : &Path) -> Option<SystemTime>
+    {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+impl Target
+{
+    // a target configured by JSON, as if its settings were the top-level settings of the configuration file
+    pub fn from_json(settings : &str) -> Target
+    {
+        Templates::load_built_in().unwrap();
+        Config::target(None, &json::parse(settings).unwrap(), "test").unwrap()
+    }
+}
+
+#[cfg(test)]
+pub mod tests
+{
+    use super::*;
+    use std::{ fs::File, sync::{ Mutex, atomic::{ AtomicUsize, Ordering }}};
+
+    // tests which set environment variables are run one at a time, as the environment is shared between threads
+    static ENV : Mutex<()> = Mutex::new(());
+
+    // an empty directory for a test's files
+    fn directory(test : &str) -> PathBuf
+    {
+        let directory = std::env::temp_dir().join(format!("festive-bot-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    // write a file, with a modification time after any previous one, regardless of the filesystem's timestamp resolution
+    fn write(path : &Path, contents : &str, seconds : u64)
+    {
+        std::fs::write(path, contents).unwrap();
+        File::options().write(true).open(path).unwrap().set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds)).unwrap();
+    }
+
+    // run with only the given environment variables set, along with the configuration file, if any
+    fn with_env<T>(file : Option<&Path>, vars : &[(&str, &str)], f : impl FnOnce() -> T) -> T
+    {
+        let _lock = ENV.lock().unwrap_or_else(|e| e.into_inner());
+        let keys  = [Var::Leaderboard, Var::Session, Var::Notify, Var::Status, Var::Config].map(|var| var.key());
+        for key in keys { std::env::remove_var(key); std::env::remove_var(format!("{key}_FILE")) }
+        if let Some(file) = file { std::env::set_var(Var::Config.key(), file) }
+        for (key, value) in vars { std::env::set_var(key, value) }
+
+        let result = f();
+        for key in keys { std::env::remove_var(key); std::env::remove_var(format!("{key}_FILE")) }
+        result
+    }
+
+    // a configuration loaded from a file containing JSON, with a leaderboard and session unless given
+    pub fn config(json : &str) -> Config
+    {
+        static FILES : AtomicUsize = AtomicUsize::new(0);
+        let mut json = json::parse(json).unwrap();
+        if json["leaderboard"].is_null() { json["leaderboard"] = "1".into() }
+        if json["session"].is_null()     { json["session"]     = "abc".into() }
+
+        Templates::load_built_in().unwrap();
+        let file   = std::env::temp_dir().join(format!("festive-bot-config-{}-{}.json", std::process::id(), FILES.fetch_add(1, Ordering::Relaxed)));
+        std::fs::write(&file, json.dump()).unwrap();
+        let config = with_env(Some(&file), &[], Config::load);
+        std::fs::remove_file(file).unwrap();
+        config.unwrap()
+    }
+
+    #[test]
+    fn changed_files_are_reloaded()
+    {
+        let directory = directory("reload");
+        let (file, session) = (directory.join("config.json"), directory.join("session"));
+        write(&file, &format!(r#"{{ "leaderboard": "1", "session": {{ "file": "{}" }} }}"#, session.display()), 1_000_000);
+        write(&session, "abc", 1_000_000);
+
+        let mut config = with_env(Some(&file), &[], Config::load).unwrap();
+        assert_eq!((config.leaderboard.as_str(), config.session.as_str()), ("1", "abc"));
+        assert!(!config.changed());
+
+        // secrets read from files are watched, as well as the configuration file itself
+        write(&session, "def", 1_000_001);
+        assert!(config.changed());
+        config = with_env(Some(&file), &[], Config::load).unwrap();
+        assert_eq!(config.session, "def");
+        assert!(!config.changed());
+
+        // a failed reload isn't retried until the files change again
+        write(&file, "{ \"leaderboard\": ", 1_000_002);
+        assert!(config.changed());
+        assert!(with_env(Some(&file), &[], Config::load).is_err());
+        config.mark_unchanged();
+        assert!(!config.changed());
+
+        write(&file, r#"{ "leaderboard": "2", "session": "ghi" }"#, 1_000_003);
+        assert!(config.changed());
+        config = with_env(Some(&file), &[], Config::load).unwrap();
+        assert_eq!((config.leaderboard.as_str(), config.session.as_str()), ("2", "ghi"));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn secrets_are_trimmed_and_must_not_be_empty()
+    {
+        let directory = directory("secrets");
+        let (secret, empty) = (directory.join("secret"), directory.join("empty"));
+        std::fs::write(&secret, "  abc\n").unwrap();
+        std::fs::write(&empty, " \n").unwrap();
+
+        assert_eq!(Source::Value(" abc ".to_string()).read("test").unwrap(), "abc");
+        assert_eq!(Source::File(secret).read("test").unwrap(), "abc");
+        assert!(matches!(Source::File(empty).read("test"),                    Err(FestiveError::Secret(_))));
+        assert!(matches!(Source::File(directory.join("missing")).read("test"), Err(FestiveError::Secret(_))));
+        assert!(matches!(Source::Value(String::new()).read("test"),           Err(FestiveError::Secret(_))));
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn secrets_are_read_from_the_output_of_commands()
+    {
+        assert_eq!(Source::Command("printf ' abc\\n' | tr a-c x-z".to_string()).read("test").unwrap(), "xyz");
+        assert!(matches!(Source::Command("echo abc; exit 1".to_string()).read("test"), Err(FestiveError::Secret(_))));
+        assert!(matches!(Source::Command("true".to_string()).read("test"),             Err(FestiveError::Secret(_))));
+    }
+
+    #[test]
+    fn secret_sources_are_given_in_the_configuration_file()
+    {
+        assert!(matches!(Config::source(&JsonValue::Null, "test"),                         Ok(None)));
+        assert!(matches!(Config::source(&"abc".into(), "test"),                            Ok(Some(Source::Value(v)))   if v == "abc"));
+        assert!(matches!(Config::source(&json::object!{ file: "/run/secret" }, "test"),    Ok(Some(Source::File(p)))    if p == Path::new("/run/secret")));
+        assert!(matches!(Config::source(&json::object!{ command: "pass aoc" }, "test"),    Ok(Some(Source::Command(c))) if c == "pass aoc"));
+        assert!(Config::source(&json::object!{ file: "/run/secret", command: "pass aoc" }, "test").is_err());
+        assert!(Config::source(&json::object!{}, "test").is_err());
+        assert!(Config::source(&1.into(), "test").is_err());
+    }
+
+    #[test]
+    fn environment_variables_take_precedence_over_files_and_the_configuration_file()
+    {
+        let directory = directory("precedence");
+        let file      = directory.join("session");
+        std::fs::write(&file, "from file").unwrap();
+
+        let session = |vars : &[(&str, &str)]| with_env(None, vars, || Config::secret(Var::Session, &"from config".into(), &mut Vec::new()).unwrap());
+        let path    = file.to_str().unwrap();
+        assert_eq!(session(&[("FESTIVE_BOT_SESSION", "from env"), ("FESTIVE_BOT_SESSION_FILE", path)]).as_deref(), Some("from env"));
+        assert_eq!(session(&[("FESTIVE_BOT_SESSION_FILE", path)]).as_deref(),                                      Some("from file"));
+        assert_eq!(session(&[]).as_deref(),                                                                        Some("from config"));
+        assert_eq!(with_env(None, &[], || Config::secret(Var::Session, &JsonValue::Null, &mut Vec::new()).unwrap()), None);
+
+        // the configuration file's path can't itself be read from a file
+        assert_eq!(Var::Config.get_file(), None);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    fn routes(json : &str) -> FestiveResult<HashMap<Route, Vec<String>>>
+    {
+        let targets = ["notify", "status", "feed"].map(|name| (name.to_string(), Target::from_json("{}"))).into_iter().collect();
+        Config::routes(&json::parse(json).unwrap(), &targets)
+    }
+
+    #[test]
+    fn routes_default_to_the_notify_and_status_targets()
+    {
+        let routes = routes("null").unwrap();
+        assert_eq!(routes.len(), Route::iter().count());
+        assert_eq!(routes[&Route::PartOne],   ["notify"]);
+        assert_eq!(routes[&Route::Standings], ["notify"]);
+        assert_eq!(routes[&Route::Heartbeat], ["status"]);
+        assert_eq!(routes[&Route::Errors],    ["status"]);
+    }
+
+    #[test]
+    fn configured_routes_replace_their_defaults()
+    {
+        let routes = routes(r#"{ "part_two": ["notify", "feed"], "heartbeat": [] }"#).unwrap();
+        assert_eq!(routes[&Route::PartOne], ["notify"]);
+        assert_eq!(routes[&Route::PartTwo], ["notify", "feed"]);
+        assert!(routes[&Route::Heartbeat].is_empty());
+        assert_eq!(routes[&Route::Status], ["status"]);
+    }
+
+    #[test]
+    fn routes_must_be_known_and_name_known_targets()
+    {
+        assert!(routes(r#"{ "part_three": ["notify"] }"#).is_err());
+        assert!(routes(r#"{ "part_one": ["notify", "missing"] }"#).is_err());
+        assert!(routes(r#"{ "part_one": "notify" }"#).is_err());
+        assert!(routes(r#"{ "part_one": [1] }"#).is_err());
+        assert!(routes(r#"["part_one"]"#).is_err());
+    }
+
+    fn utc(y : i32, mo : u32, d : u32, h : u32, mi : u32) -> DateTime<Utc>
+    {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn quiet_hours_within_a_day()
+    {
+        let target = Target::from_json(r#"{ "quiet": { "start": "09:00", "end": "17:00" } }"#);
+        assert_eq!(target.quiet_until(&utc(2025, 12, 1, 8, 59)), None);
+        assert_eq!(target.quiet_until(&utc(2025, 12, 1, 9, 0)),  Some(utc(2025, 12, 1, 17, 0)));
+        assert_eq!(target.quiet_until(&utc(2025, 12, 1, 17, 0)), None);
+    }
+
+    #[test]
+    fn quiet_hours_spanning_midnight_in_local_time()
+    {
+        let target = Target::from_json(r#"{ "timezone": "Europe/Paris", "quiet": { "start": "22:00", "end": "07:00" } }"#);
+        assert_eq!(target.quiet_until(&utc(2025, 12, 1, 20, 59)), None);
+        assert_eq!(target.quiet_until(&utc(2025, 12, 1, 21, 0)),  Some(utc(2025, 12, 2, 6, 0)));
+        assert_eq!(target.quiet_until(&utc(2025, 12, 2, 5, 59)),  Some(utc(2025, 12, 2, 6, 0)));
+        assert_eq!(target.quiet_until(&utc(2025, 12, 2, 6, 0)),   None);
+    }
+
+    #[test]
+    fn quiet_hours_ending_in_a_daylight_saving_gap_end_an_hour_later()
+    {
+        // 02:30 doesn't exist in Paris on the 30th of March 2025, so quiet hours end at 03:30 CEST
+        let target = Target::from_json(r#"{ "timezone": "Europe/Paris", "quiet": { "start": "22:00", "end": "02:30" } }"#);
+        assert_eq!(target.quiet_until(&utc(2025, 3, 29, 22, 0)), Some(utc(2025, 3, 30, 1, 30)));
+    }
+
+    #[test]
+    fn quiet_hours_must_differ()
+    {
+        let json = json::parse(r#"{ "quiet": { "start": "22:00", "end": "22:00" } }"#).unwrap();
+        assert!(Config::target(None, &json, "test").is_err());
+        let json = json::parse(r#"{ "quiet": { "start": "22", "end": "07:00" } }"#).unwrap();
+        assert!(Config::target(None, &json, "test").is_err());
+    }
+}
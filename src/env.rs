@@ -2,8 +2,8 @@ use chrono::Duration;
 use crate::error::{ FestiveResult, FestiveError };
 
 // environment variable handles
-#[derive(Debug)]
-pub enum Var { Leaderboard, Session, Notify, Status }
+#[derive(Debug, Clone, Copy)]
+pub enum Var { Leaderboard, Session, Notify, Status, Config }
 
 impl Var
 {
@@ -14,7 +14,8 @@ impl Var
             Var::Leaderboard => "FESTIVE_BOT_LEADERBOARD",
             Var::Session     => "FESTIVE_BOT_SESSION",
             Var::Notify      => "FESTIVE_BOT_NOTIFY",
-            Var::Status      => "FESTIVE_BOT_STATUS"
+            Var::Status      => "FESTIVE_BOT_STATUS",
+            Var::Config      => "FESTIVE_BOT_CONFIG"
         }
     }
 
@@ -22,15 +23,33 @@ impl Var
     {
         std::env::var(self.key()).map_err(|_| FestiveError::Var(self))
     }
+
+    // the path of a file containing this variable's value, from the variant of this variable with the _FILE suffix
+    // useful for container secret mounts, which avoid exposing the value in the process environment
+    pub fn get_file(self) -> Option<String>
+    {
+        match self
+        {
+            Var::Config => None,
+            _           => std::env::var(format!("{}_FILE", self.key())).ok()
+        }
+    }
 }
 
 // command-line arguments
 pub struct Args
 {
     pub all_years: bool,
+    pub digest:    bool,
     pub period:    Duration,
     pub standings: Duration,
-    pub heartbeat: Option<Duration>
+    pub teams:     Duration,
+    pub results:   Duration,
+    pub heartbeat: Option<Duration>,
+    pub refresh:   Duration,
+    pub backoff:   Option<Duration>,
+    pub busy:      Duration,
+    pub countdown: Vec<Duration>
 }
 
 // useful durations in minutes
@@ -38,10 +57,13 @@ const HOUR : i64 = 60;
 const DAY  : i64 = HOUR * 24;
 const WEEK : i64 = DAY  * 7;
 
+// the gap between the last puzzle unlock of one year and the first of the next, in a non-leap year
+const OFFSEASON : i64 = DAY * (365 - 24);
+
 // options passed as command-line arguments
 // also used as states for the argument parser
 #[derive(Clone, Copy)]
-enum Opt { AllYears, Period, Standings, Heartbeat }
+enum Opt { AllYears, Digest, Period, Standings, Teams, Results, Heartbeat, Refresh, Backoff, Busy, Countdown }
 
 impl Opt
 {
@@ -51,9 +73,16 @@ impl Opt
         match self
         {
             Opt::AllYears  => "[--all-years]",
+            Opt::Digest    => "[--digest]",
             Opt::Period    => "[--period mins]",
             Opt::Standings => "[--standings mins]",
-            Opt::Heartbeat => "[--heartbeat mins]"
+            Opt::Teams     => "[--teams mins]",
+            Opt::Results   => "[--results mins]",
+            Opt::Heartbeat => "[--heartbeat mins]",
+            Opt::Refresh   => "[--refresh mins]",
+            Opt::Backoff   => "[--backoff mins]",
+            Opt::Busy      => "[--busy mins]",
+            Opt::Countdown => "[--countdown mins]..."
         }
     }
 
@@ -65,8 +94,8 @@ impl Opt
 
         match self
         {
-            // no error message as there are no parameters for --all-years
-            Opt::AllYears => (),
+            // no error message as there are no parameters for --all-years or --digest
+            Opt::AllYears | Opt::Digest => (),
 
             // the mins parameter of --period
             Opt::Period =>
@@ -84,12 +113,60 @@ impl Opt
                 println!("- If unset, the default value is {DAY} (one day).");
             },
 
+            // the mins parameter of --teams
+            Opt::Teams =>
+            {
+                println!("- The mins parameter should be a positive integer, representing the interval between team standings announcements in minutes.");
+                println!("- It must be a multiple of the iteration period (see --period), and be no larger than {WEEK} (one week).");
+                println!("- If unset, the default value is {DAY} (one day).");
+            },
+
+            // the mins parameter of --results
+            Opt::Results =>
+            {
+                println!("- The mins parameter should be a positive integer, representing the delay between each puzzle unlocking and its results being announced in minutes.");
+                println!("- It must be a multiple of the iteration period (see --period), and be no larger than {DAY} (one day).");
+                println!("- If unset, the default value is {DAY} (one day), announcing each puzzle's results as the next one unlocks.");
+            },
+
             // the mins parameter of --heartbeat
             Opt::Heartbeat =>
             {
                 println!("- The mins parameter should be a positive integer, representing the interval between heartbeat messages in minutes.");
                 println!("- It must be a multiple of the iteration period (see --period), and be no larger than {WEEK} (one week).");
                 println!("- If unset, no heartbeat messages are sent.");
+            },
+
+            // the mins parameter of --refresh
+            Opt::Refresh =>
+            {
+                println!("- The mins parameter should be a positive integer, representing the interval between requests for past years' leaderboards in minutes.");
+                println!("- It must be a multiple of the iteration period (see --period).");
+                println!("- If unset, the default value is {DAY} (one day).");
+            },
+
+            // the mins parameter of --backoff
+            Opt::Backoff =>
+            {
+                println!("- The mins parameter should be a positive integer, representing the iteration period in minutes when not busy after a puzzle unlock.");
+                println!("- It must be a multiple of the iteration period (see --period), and divide evenly into {DAY} (one day).");
+                println!("- If unset, the iteration period is fixed, and no backing off occurs.");
+            },
+
+            // the mins parameter of --busy
+            Opt::Busy =>
+            {
+                println!("- The mins parameter should be a positive integer, representing how long after each puzzle unlock to use the iteration period before backing off.");
+                println!("- It must be a multiple of the iteration period (see --period), and be no larger than {DAY} (one day).");
+                println!("- If unset, the default value is {} (four hours).", 4 * HOUR);
+            },
+
+            // the mins parameter of --countdown
+            Opt::Countdown =>
+            {
+                println!("- The mins parameter should be a positive integer, representing how long before the first puzzle unlock of the year to make a countdown announcement.");
+                println!("- It must be a multiple of the iteration period (see --period), be no larger than {OFFSEASON} (the gap between years), and may be given multiple times.");
+                println!("- If unset, no countdown announcements are made.");
             }
         };
         std::process::exit(1);
@@ -99,9 +176,16 @@ impl Opt
     fn iter() -> impl Iterator<Item = Opt>
     {
         [Opt::AllYears,
+         Opt::Digest,
          Opt::Period,
          Opt::Standings,
-         Opt::Heartbeat].into_iter()
+         Opt::Teams,
+         Opt::Results,
+         Opt::Heartbeat,
+         Opt::Refresh,
+         Opt::Backoff,
+         Opt::Busy,
+         Opt::Countdown].into_iter()
     }
 }
 
@@ -120,9 +204,16 @@ impl Args
         Args
         {
             all_years: false,
+            digest:    false,
             period:    Duration::minutes(HOUR),
             standings: Duration::minutes(DAY),
-            heartbeat: None
+            teams:     Duration::minutes(DAY),
+            results:   Duration::minutes(DAY),
+            heartbeat: None,
+            refresh:   Duration::minutes(DAY),
+            backoff:   None,
+            busy:      Duration::minutes(4 * HOUR),
+            countdown: Vec::new()
         }
     }
 
@@ -134,15 +225,28 @@ impl Args
         let mut state          = None;
         let mut mins_period    = current.period.num_minutes();
         let mut mins_standings = current.standings.num_minutes();
+        let mut mins_teams     = current.teams.num_minutes();
+        let mut mins_results   = current.results.num_minutes();
         let mut mins_heartbeat = None;
+        let mut mins_refresh   = current.refresh.num_minutes();
+        let mut mins_backoff   = None;
+        let mut mins_busy      = current.busy.num_minutes();
+        let mut mins_countdown = Vec::new();
         for arg in std::env::args().skip(1)
         {
             match (arg.as_str(), state)
             {
                 ("--all-years", None) => current.all_years = true,
+                ("--digest",    None) => current.digest    = true,
                 ("--period",    None) => state             = Some(Opt::Period),
                 ("--standings", None) => state             = Some(Opt::Standings),
+                ("--teams",     None) => state             = Some(Opt::Teams),
+                ("--results",   None) => state             = Some(Opt::Results),
                 ("--heartbeat", None) => state             = Some(Opt::Heartbeat),
+                ("--refresh",   None) => state             = Some(Opt::Refresh),
+                ("--backoff",   None) => state             = Some(Opt::Backoff),
+                ("--busy",      None) => state             = Some(Opt::Busy),
+                ("--countdown", None) => state             = Some(Opt::Countdown),
 
                 // parse mins parameter for --period
                 (mins, Some(s@Opt::Period)) =>
@@ -158,6 +262,20 @@ impl Args
                     state          = None;
                 },
 
+                // parse mins parameter for --teams
+                (mins, Some(s@Opt::Teams)) =>
+                {
+                    mins_teams = mins.parse::<i64>().ok().filter(|&m| 0 < m && m <= WEEK).unwrap_or_else(|| s.error());
+                    state      = None;
+                },
+
+                // parse mins parameter for --results
+                (mins, Some(s@Opt::Results)) =>
+                {
+                    mins_results = mins.parse::<i64>().ok().filter(|&m| 0 < m && m <= DAY).unwrap_or_else(|| s.error());
+                    state        = None;
+                },
+
                 // parse mins parameter for --heartbeat
                 (mins, Some(s@Opt::Heartbeat)) =>
                 {
@@ -165,6 +283,34 @@ impl Args
                     state          = None;
                 },
 
+                // parse mins parameter for --refresh
+                (mins, Some(s@Opt::Refresh)) =>
+                {
+                    mins_refresh = mins.parse::<i64>().ok().filter(|&m| 0 < m).unwrap_or_else(|| s.error());
+                    state        = None;
+                },
+
+                // parse mins parameter for --backoff
+                (mins, Some(s@Opt::Backoff)) =>
+                {
+                    mins_backoff = Some(mins.parse::<i64>().ok().filter(|&m| 0 < m && DAY % m == 0).unwrap_or_else(|| s.error()));
+                    state        = None;
+                },
+
+                // parse mins parameter for --busy
+                (mins, Some(s@Opt::Busy)) =>
+                {
+                    mins_busy = mins.parse::<i64>().ok().filter(|&m| 0 < m && m <= DAY).unwrap_or_else(|| s.error());
+                    state     = None;
+                },
+
+                // parse mins parameter for --countdown
+                (mins, Some(s@Opt::Countdown)) =>
+                {
+                    mins_countdown.push(mins.parse::<i64>().ok().filter(|&m| 0 < m && m <= OFFSEASON).unwrap_or_else(|| s.error()));
+                    state = None;
+                },
+
                 // unexpected argument
                 (arg, _) =>
                 {
@@ -178,13 +324,25 @@ impl Args
         // if state isn't None after parsing concludes, a parameter wasn't parsed
         if let Some(s) = state { s.error() }
 
-        // now the actual iteration period is known, ensure --standings and --heartbeat parameters are multiples of it
+        // now the actual iteration period is known, ensure the other parameters are multiples of it
         if                                      mins_standings % mins_period != 0 { Opt::Standings.error() }
+        if                                      mins_teams     % mins_period != 0 { Opt::Teams.error()     }
+        if                                      mins_results   % mins_period != 0 { Opt::Results.error()   }
         if let Some(mins) = mins_heartbeat { if mins           % mins_period != 0 { Opt::Heartbeat.error() }}
+        if                                      mins_refresh   % mins_period != 0 { Opt::Refresh.error()   }
+        if let Some(mins) = mins_backoff   { if mins           % mins_period != 0 { Opt::Backoff.error()   }}
+        if                                      mins_busy      % mins_period != 0 { Opt::Busy.error()      }
+        if mins_countdown.iter().any(|mins|     mins           % mins_period != 0)  { Opt::Countdown.error() }
 
         current.period    = Duration::minutes(mins_period);
         current.standings = Duration::minutes(mins_standings);
+        current.teams     = Duration::minutes(mins_teams);
+        current.results   = Duration::minutes(mins_results);
         current.heartbeat = mins_heartbeat.map(Duration::minutes);
+        current.refresh   = Duration::minutes(mins_refresh);
+        current.backoff   = mins_backoff.map(Duration::minutes);
+        current.busy      = Duration::minutes(mins_busy);
+        current.countdown = mins_countdown.into_iter().map(Duration::minutes).collect();
         current
     }
 }
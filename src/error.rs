@@ -7,6 +7,7 @@ pub type FestiveResult<T> = Result<T, FestiveError>;
 pub enum FestiveError
 {
     Var(Var),
+    Secret(String),
     Init,
     Conv,
     File,
@@ -20,12 +21,13 @@ impl std::fmt::Display for FestiveError
     {
         match self
         {
-            FestiveError::Var(v) => write!(f, "required environment variable {} is unset", v.key()),
-            FestiveError::Init   => write!(f, "initialisation error"),
-            FestiveError::Conv   => write!(f, "conversion error"),
-            FestiveError::File   => write!(f, "filesystem error"),
-            FestiveError::Http   => write!(f, "HTTP error"),
-            FestiveError::Parse  => write!(f, "parse error")
+            FestiveError::Var(v)    => write!(f, "required environment variable {} is unset and missing from the configuration file", v.key()),
+            FestiveError::Secret(n) => write!(f, "failed to read the value of {n} from a file or command"),
+            FestiveError::Init      => write!(f, "initialisation error"),
+            FestiveError::Conv      => write!(f, "conversion error"),
+            FestiveError::File      => write!(f, "filesystem error"),
+            FestiveError::Http      => write!(f, "HTTP error"),
+            FestiveError::Parse     => write!(f, "parse error")
         }
     }
 }
@@ -1,10 +1,10 @@
-use std::{ collections::HashMap, fmt::Write };
+use std::{ cmp::Ordering, collections::{ HashMap, BTreeMap }, fmt::Write };
 use json::JsonValue;
 use chrono::{ DateTime, Utc, FixedOffset, TimeZone, Duration, DurationRound };
-use reqwest::{ blocking::Client, StatusCode };
+use reqwest::{ blocking::Client, StatusCode, header::{ ETAG, LAST_MODIFIED, IF_NONE_MATCH, IF_MODIFIED_SINCE }};
 use num_rational::BigRational;
 use num_traits::{ identities, FromPrimitive, ToPrimitive };
-use crate::error::{ FestiveResult, FestiveError };
+use crate::{ cache::Cache, config::{ Config, Target, Member, Aggregate }, locale::Locale, streaks::Streak, template::Message, webhook::{ Webhook, Route }, error::{ FestiveResult, FestiveError }};
 
 // puzzle completion events parsed from AoC API
 // year and day fields match corresponding components of DateTime<Utc>
@@ -19,11 +19,96 @@ pub struct Event
 }
 
 // unique identifier for a participant on this leaderboard
+// the name is the member's alias, if configured, otherwise their AoC name
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct Identifier
 {
-    name:    String,
-    numeric: u64
+    name:      String,
+    numeric:   u64,
+    discord:   Option<u64>,
+    spectator: bool,
+    team:      Option<String>
+}
+
+// a participant's total score, and their star count for each day
+type Histogram = (BigRational, HashMap<u8, u8>);
+
+// a participant's position on the leaderboard, their total score, and the timestamp of each star they've completed, by day and star
+// the solve time is the total time taken to complete each star since its puzzle unlocked
+// streaks are as of the time of the iteration the ranking is made in, and first solves are the puzzle parts they completed before any other ranked participant
+pub struct Standing<'a>
+{
+    pub position:     Option<usize>,
+    pub name:         &'a str,
+    pub member:       u64,
+    pub spectator:    bool,
+    pub team:         Option<&'a str>,
+    pub score:        BigRational,
+    pub completions:  BTreeMap<(u32, u8), DateTime<Utc>>,
+    pub solve_time:   Duration,
+    pub streak:       Streak,
+    pub first_solves: usize
+}
+
+// rules for ordering participants with equal scores, applied in turn until one separates them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreaker { Stars, FinalStar, SolveTime, GoldStars }
+
+impl Standing<'_>
+{
+    // number of stars completed on a day
+    pub fn stars(&self, day : u32) -> usize
+    {
+        self.completions.range((day, 0) ..= (day, u8::MAX)).count()
+    }
+
+    // number of days with both stars completed
+    pub fn gold_stars(&self) -> usize
+    {
+        self.completions.keys().filter(|&&(_, star)| star == 2).count()
+    }
+
+    // star grid for the text standings, with a cell for each day
+    pub fn grid(&self) -> String
+    {
+        format!("[{} ]", (1 ..= 25).map(|d| match self.stars(d) { 0 => " -", 1 => " □", _ => " ■" }).collect::<String>())
+    }
+
+    // current and longest streaks, once any puzzle has been completed on its release day, and first solves, if any, following the star grid
+    pub fn notes(&self, locale : Locale) -> Option<String>
+    {
+        let streak = (self.streak.longest > 0).then(|| locale.streak(self.streak.current, self.streak.longest));
+        let firsts = (self.first_solves   > 0).then(|| locale.first_solves(self.first_solves));
+        let notes  = streak.into_iter().chain(firsts).collect::<Vec<_>>();
+        (!notes.is_empty()).then(|| format!("({})", notes.join(", ")))
+    }
+}
+
+impl TieBreaker
+{
+    pub fn parse(name : &str) -> Option<TieBreaker>
+    {
+        match name
+        {
+            "most_stars"          => Some(TieBreaker::Stars),
+            "earliest_final_star" => Some(TieBreaker::FinalStar),
+            "total_solve_time"    => Some(TieBreaker::SolveTime),
+            "most_gold_stars"     => Some(TieBreaker::GoldStars),
+            _                     => None
+        }
+    }
+
+    // the participant ranked higher by this rule is ordered first
+    fn compare(self, a : &Standing, b : &Standing) -> Ordering
+    {
+        match self
+        {
+            TieBreaker::Stars     => b.completions.len().cmp(&a.completions.len()),
+            TieBreaker::FinalStar => a.completions.values().max().cmp(&b.completions.values().max()),
+            TieBreaker::SolveTime => a.solve_time.cmp(&b.solve_time),
+            TieBreaker::GoldStars => b.gold_stars().cmp(&a.gold_stars())
+        }
+    }
 }
 
 impl Event
@@ -33,6 +118,26 @@ impl Event
         &self.timestamp
     }
 
+    pub fn star(&self) -> u8
+    {
+        self.star
+    }
+
+    pub fn year(&self) -> i32
+    {
+        self.year
+    }
+
+    pub fn day(&self) -> u32
+    {
+        self.day
+    }
+
+    pub fn member(&self) -> u64
+    {
+        self.id.numeric
+    }
+
     // use UTC timestamps, but truncate centered on UTC-05:00 (EST), as this is when puzzles unlock
     pub fn trunc_ts(ts : &DateTime<Utc>, dur : Duration) -> FestiveResult<DateTime<Utc>>
     {
@@ -45,18 +150,111 @@ impl Event
     }
 
     // not using Display trait so FestiveResult can be returned
-    pub fn fmt(&self) -> FestiveResult<String>
+    // members with Discord user IDs configured may optionally be mentioned instead of named
+    // the first completion of each puzzle part among all of the year's events is announced differently
+    pub fn fmt(&self, events : &[Event], target : &Target, mentions : bool) -> FestiveResult<String>
+    {
+        let emoji   = Self::emoji(self.star)?;
+        let score   = self.score()?;
+        let message = if self.first(events) { Message::FirstCompletion } else { Message::Completion };
+        Ok(target.render(message, &[("year",    &self.year),
+                                    ("name",    &self.name(mentions)),
+                                    ("day",     &format!("{:02}", self.day)),
+                                    ("part",    &target.locale.part(self.star)),
+                                    ("emoji",   &emoji),
+                                    ("score",   &score),
+                                    ("points",  &target.locale.points(&score)),
+                                    ("elapsed", &self.elapsed()?)]))
+    }
+
+    // combine many puzzle completion events into a single message, grouped by day and part
+    // first completions are determined among all of the year's events, not only those being combined
+    pub fn digest<'a>(year : i32, events : impl Iterator<Item = &'a Event>, all : &[Event], target : &Target, mentions : bool) -> FestiveResult<String>
     {
-        let (part, stars) = match self.star
+        let mut groups : BTreeMap<(u32, u8), Vec<String>> = BTreeMap::new();
+        for e in events
         {
-            1 => ("one", ":star:"),
-            2 => ("two", ":star: :star:"),
-            _ => return Err(FestiveError::Parse)
-        };
+            let score   = e.score()?;
+            let message = if e.first(all) { Message::DigestFirst } else { Message::DigestEntry };
+            groups.entry((e.day, e.star)).or_default().push(target.render(message, &[("name",    &e.name(mentions)),
+                                                                                      ("score",   &score),
+                                                                                      ("points",  &target.locale.points(&score)),
+                                                                                      ("elapsed", &e.elapsed()?)]));
+        }
+
+        let mut digest = target.render(Message::DigestHeader, &[("year", &year)]);
+        for ((day, star), entries) in groups
+        {
+            digest.push('\n');
+            digest.push_str(&target.render(Message::DigestLine, &[("year",    &year),
+                                                                  ("day",     &format!("{day:02}")),
+                                                                  ("part",    &target.locale.part(star)),
+                                                                  ("emoji",   &Self::emoji(star)?),
+                                                                  ("entries", &entries.join(", "))]));
+        }
+        Ok(digest)
+    }
+
+    // a digest for each target that any of the events are routed to, containing only the parts routed to that target
+    // a digest including any part two completions is routed as such, for quiet hours and JSON feeds
+    pub fn digests<'a>(year : i32, events : &[&Event], all : &[Event], config : &'a Config) -> FestiveResult<Vec<(Webhook<'a>, Route, String)>>
+    {
+        let mut webhooks = config.route(Route::PartOne).chain(config.route(Route::PartTwo)).collect::<Vec<_>>();
+        webhooks.sort_by_key(|w| w.name);
+        webhooks.dedup_by_key(|w| w.name);
 
-        let score  = self.score()?;
-        let plural = if score == identities::one() { "" } else { "s" };
-        Ok(format!(":christmas_tree: [{}] {} has completed puzzle {:02}, part {part}, scoring {score} point{plural}! {stars}", self.year, self.id.name, self.day))
+        let mut digests = Vec::new();
+        for webhook in webhooks
+        {
+            let routed = events.iter().copied().filter(|e| config.route(Route::part(e.star)).any(|w| w.name == webhook.name)).collect::<Vec<_>>();
+            let Some(star) = routed.iter().map(|e| e.star).max() else { continue };
+            digests.push((webhook, Route::part(star), Self::digest(year, routed.into_iter(), all, webhook.target, config.mentions)?));
+        }
+        Ok(digests)
+    }
+
+    // whether this is the earliest completion of its puzzle part by a ranked participant, including any completed at the same time
+    fn first(&self, events : &[Event]) -> bool
+    {
+        !self.id.spectator && events.iter().filter(|e| !e.id.spectator && e.year == self.year && (e.day, e.star) == (self.day, self.star)).map(|e| e.timestamp).min() == Some(self.timestamp)
+    }
+
+    // time taken to complete this puzzle since it unlocked
+    fn elapsed(&self) -> FestiveResult<String>
+    {
+        Self::elapsed_since_unlock(self.year, self.day, &self.timestamp)
+    }
+
+    pub fn elapsed_since_unlock(year : i32, day : u32, ts : &DateTime<Utc>) -> FestiveResult<String>
+    {
+        Ok(Self::clock(*ts - Self::puzzle_unlock(year, day)?))
+    }
+
+    // hours, minutes, and seconds, preceded by days when at least one day
+    fn clock(dur : Duration) -> String
+    {
+        let secs = dur.num_seconds();
+        let hms  = format!("{:02}:{:02}:{:02}", secs / 3600 % 24, secs / 60 % 60, secs % 60);
+        if secs < 86400 { hms } else { format!("{}d {hms}", secs / 86400) }
+    }
+
+    fn emoji(star : u8) -> FestiveResult<&'static str>
+    {
+        match star
+        {
+            1 => Ok(":star:"),
+            2 => Ok(":star: :star:"),
+            _ => Err(FestiveError::Parse)
+        }
+    }
+
+    pub fn name(&self, mentions : bool) -> String
+    {
+        match self.id.discord
+        {
+            Some(discord) if mentions => format!("<@{discord}>"),
+            _                         => self.id.name.clone()
+        }
     }
 
     // custom scoring based on the reciprocal of full days since the puzzle was released
@@ -74,43 +272,82 @@ impl Event
         Utc.with_ymd_and_hms(year, 12, day, 5, 0, 0).single().ok_or(FestiveError::Conv)
     }
 
-    pub fn request(year : i32, leaderboard : &str, session : &str, client : &Client) -> FestiveResult<String>
+    // AoC asks that leaderboards are requested no more than once every 15 minutes
+    pub fn request_limit() -> Duration
     {
-        let url = format!("https://adventofcode.com/{year}/leaderboard/private/view/{leaderboard}.json");
+        Duration::minutes(15)
+    }
 
-        // send HTTP request
-        let response = client.get(url)
-                             .header("cookie", format!("session={session}"))
-                             .send()
-                             .map_err(|_| FestiveError::Http)?;
+    // responses are cached on disk, and only requested again once the refresh duration has elapsed
+    // the cached response is used regardless of refresh if the request limit would otherwise be exceeded
+    // both are measured using the actual time of each request, not the scheduled time of the iteration, in case iterations overrun
+    pub fn request(year : i32, leaderboard : &str, session : &str, refresh : Duration, client : &Client) -> FestiveResult<String>
+    {
+        let cache = Cache::read(year, leaderboard);
+        match cache.as_ref().map(|c| (c, c.wait(&Utc::now(), refresh)))
+        {
+            Some((cache, None)) =>
+            {
+                println!("using response cached at {}", cache.fetched);
+                return Ok(cache.body.clone())
+            },
+            Some((_, Some(wait))) => std::thread::sleep(wait.to_std().map_err(|_| FestiveError::Conv)?),
+            None                  => ()
+        }
+
+        // send HTTP request, conditional on the cached response's validators, if any
+        let url     = format!("https://adventofcode.com/{year}/leaderboard/private/view/{leaderboard}.json");
+        let mut req = client.get(url).header("cookie", format!("session={session}"));
+        if let Some(etag)          = cache.as_ref().and_then(|c| c.etag.as_ref())          { req = req.header(IF_NONE_MATCH,     etag)          }
+        if let Some(last_modified) = cache.as_ref().and_then(|c| c.last_modified.as_ref()) { req = req.header(IF_MODIFIED_SINCE, last_modified) }
+        let fetched  = Utc::now();
+        let response = req.send().map_err(|_| FestiveError::Http)?;
 
-        match response.status()
+        let fresh = match (response.status(), cache)
         {
-            // expected response, get the text from the payload
-            StatusCode::OK => response.text().map_err(|_| FestiveError::Http),
+            // cached response is still valid
+            (StatusCode::NOT_MODIFIED, Some(cache)) =>
+            {
+                println!("cached response not modified");
+                Cache { fetched, ..cache }
+            },
+
+            // expected response, get the text from the payload along with its validators
+            (StatusCode::OK, _) =>
+            {
+                let header              = |name| response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+                let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+                Cache { fetched, etag, last_modified, body: response.text().map_err(|_| FestiveError::Http)? }
+            },
 
             // AoC responds with INTERNAL_SERVER_ERROR when the session cookie is invalid
-            StatusCode::INTERNAL_SERVER_ERROR =>
+            (StatusCode::INTERNAL_SERVER_ERROR, _) =>
             {
                 println!("the session cookie might have expired");
-                Err(FestiveError::Http)
+                return Err(FestiveError::Http)
             }
 
             // unexpected status code
-            _ => Err(FestiveError::Http)
-        }
+            _ => return Err(FestiveError::Http)
+        };
+
+        fresh.write(year, leaderboard)?;
+        Ok(fresh.body)
     }
 
-    pub fn parse(response : &str, events : &mut Vec<Event>) -> FestiveResult<()>
+    pub fn parse(response : &str, config : &BTreeMap<u64, Member>, events : &mut Vec<Event>, members : &mut BTreeMap<u64, String>) -> FestiveResult<()>
     {
         // the response should be valid JSON
         let json = json::parse(response).map_err(|_| FestiveError::Parse)?;
 
-        // iterate through the JSON, collating individual puzzle completion events
+        // iterate through the JSON, collating individual puzzle completion events, and the names of all members
         events.clear();
+        members.clear();
         for (id, member) in json["members"].entries()
         {
-            let name = match &member["name"]
+            let numeric    = id.parse().map_err(|_| FestiveError::Parse)?;
+            let configured = config.get(&numeric);
+            let aoc_name   = match &member["name"]
             {
                 // anonymous users appear with null names in the AoC API
                 JsonValue::Null         => format!("anonymous user #{id}"),
@@ -119,6 +356,17 @@ impl Event
                 _                       => return Err(FestiveError::Parse)
             };
 
+            // the snapshot of members records their names from the AoC API, so that renames are detected regardless of aliases
+            // it includes ignored members, so that ignoring a member isn't mistaken for them leaving, but they have no events
+            members.insert(numeric, aoc_name.clone());
+            if configured.is_some_and(|m| m.ignore) { continue }
+
+            // configured aliases take precedence over names from the AoC API everywhere else
+            let name      = configured.and_then(|m| m.alias.clone()).unwrap_or(aoc_name);
+            let discord   = configured.and_then(|m| m.discord);
+            let spectator = configured.is_some_and(|m| m.spectator);
+            let team      = configured.and_then(|m| m.team.clone());
+
             for (day, stars) in member["completion_day_level"].entries()
             {
                 for (star, contents) in stars.entries()
@@ -129,7 +377,7 @@ impl Event
                         year:      json["event"].to_string().parse().map_err(|_| FestiveError::Parse)?,
                         day:       day.parse().map_err(|_| FestiveError::Parse)?,
                         star:      star.parse().map_err(|_| FestiveError::Parse)?,
-                        id:        Identifier { name: name.clone(), numeric: id.parse().map_err(|_| FestiveError::Parse)? }
+                        id:        Identifier { name: name.clone(), numeric, discord, spectator, team: team.clone() }
                     });
                 }
             }
@@ -140,61 +388,427 @@ impl Event
         Ok(())
     }
 
-    pub fn standings(events : &[Event]) -> FestiveResult<String>
+    // positions and scores are formatted for the locale
+    // rank participants by score descending, then by each tie-breaker in turn, then by name ascending
+    // participants with equal scores which aren't separated by any tie-breaker share a position, and spectators have no position
+    // spectators are listed after the ranked participants
+    pub fn ranking<'a>(events : &'a [Event], rules : &[TieBreaker], current : &DateTime<Utc>) -> FestiveResult<Vec<Standing<'a>>>
     {
-        // score histogram
-        let mut hist : HashMap<&Identifier, (BigRational, HashMap<u8, u8>)> = HashMap::new();
+        // the earliest completion of each puzzle part by a ranked participant
+        let mut firsts : HashMap<(u32, u8), DateTime<Utc>> = HashMap::new();
+        for e in events.iter().filter(|e| !e.id.spectator)
+        {
+            let first = firsts.entry((e.day, e.star)).or_insert(e.timestamp);
+            *first    = (*first).min(e.timestamp);
+        }
+
+        let mut standings : HashMap<&Identifier, Standing> = HashMap::new();
         for e in events
         {
-            let (score, stars) = hist.entry(&e.id).or_insert_with(|| (identities::zero(), HashMap::new()));
-            *score                                 += e.score()?;
-            *stars.entry(e.day as u8).or_insert(0) += 1;
+            let standing = standings.entry(&e.id).or_insert_with(|| Standing
+            {
+                position:     None,
+                name:         &e.id.name,
+                member:       e.id.numeric,
+                spectator:    e.id.spectator,
+                team:         e.id.team.as_deref(),
+                score:        identities::zero(),
+                completions:  BTreeMap::new(),
+                solve_time:   Duration::zero(),
+                streak:       Streak::default(),
+                first_solves: 0
+            });
+            standing.score      += e.score()?;
+            standing.solve_time += e.timestamp - Self::puzzle_unlock(e.year, e.day)?;
+            standing.completions.insert((e.day, e.star), e.timestamp);
+            if !e.id.spectator && firsts.get(&(e.day, e.star)) == Some(&e.timestamp) { standing.first_solves += 1 }
         }
 
-        // sort by score descending, then by star count descending, then by Identifier ascending
-        // group distinct scores
-        let mut scores = hist.into_iter().collect::<Vec<_>>();
-        scores.sort_unstable_by_key(|(id, (score, stars))| (-score, u8::MAX - stars.values().sum::<u8>(), *id));
-        let distinct = scores.chunk_by(|a, b| a.1 == b.1).collect::<Vec<_>>();
+        if let Some(first) = events.first()
+        {
+            let streaks = Streak::of(first.year, events, current)?;
+            for standing in standings.values_mut() { standing.streak = streaks.get(&standing.member).copied().unwrap_or_default() }
+        }
+
+        let tie_break   = |a : &Standing, b : &Standing| rules.iter().fold(Ordering::Equal, |order, rule| order.then_with(|| rule.compare(a, b)));
+        let mut ranking = standings.into_values().collect::<Vec<_>>();
+        ranking.sort_unstable_by(|a, b| (a.spectator, -&a.score).cmp(&(b.spectator, -&b.score))
+                                        .then_with(|| tie_break(a, b))
+                                        .then_with(|| (a.name, a.member).cmp(&(b.name, b.member))));
+
+        let mut pos = 1;
+        for ix in 0 .. ranking.iter().filter(|s| !s.spectator).count()
+        {
+            let tied = ix > 0 && ranking[ix - 1].score == ranking[ix].score && tie_break(&ranking[ix - 1], &ranking[ix]).is_eq();
+            if !tied { pos = ix + 1 }
+            ranking[ix].position = Some(pos);
+        }
+        Ok(ranking)
+    }
+
+    // positions and scores are formatted for the target's locale
+    // followed by footnotes explaining any ties
+    pub fn standings(events : &[Event], current : &DateTime<Utc>, target : &Target) -> FestiveResult<String>
+    {
+        let ranking = Self::ranking(events, &target.tie_breakers, current)?;
+
+        // generate standings report, with one line per participant
+        // spectators are listed separately after the ranked participants, without positions
+        let mut report = String::new();
+        for (ix, (standing, columns)) in ranking.iter().zip(Self::columns(&ranking, target.locale)?).enumerate()
+        {
+            if standing.spectator && ix > 0 && !ranking[ix - 1].spectator { writeln!(&mut report).map_err(|_| FestiveError::Conv)? }
+            writeln!(&mut report, "{columns}  {}{}", standing.grid(), standing.notes(target.locale).map(|s| format!("  {s}")).unwrap_or_default()).map_err(|_| FestiveError::Conv)?;
+        }
+
+        let footnotes = Self::footnotes(&ranking, target)?;
+        if !footnotes.is_empty() { writeln!(&mut report).map_err(|_| FestiveError::Conv)? }
+        for footnote in footnotes
+        {
+            writeln!(&mut report, "* {footnote}").map_err(|_| FestiveError::Conv)?;
+        }
+        Ok(report)
+    }
+
+    // results of a single puzzle, listing participants with both stars in order of completion, with their times since the puzzle unlocked and between its parts
+    // followed by participants with only the first star, in order of completion
+    // spectators are listed without positions
+    pub fn results(year : i32, day : u32, events : &[Event], target : &Target) -> FestiveResult<String>
+    {
+        let mut stars : HashMap<&Identifier, [Option<DateTime<Utc>> ; 2]> = HashMap::new();
+        for e in events.iter().filter(|e| e.year == year && e.day == day)
+        {
+            let part = match e.star { 1 | 2 => e.star as usize - 1, _ => return Err(FestiveError::Parse) };
+            stars.entry(&e.id).or_default()[part] = Some(e.timestamp);
+        }
+
+        let mut gold = stars.iter().filter_map(|(id, [one, two])| Some((two.as_ref()?, one.as_ref()?, *id))).collect::<Vec<_>>();
+        let mut silver = stars.iter().filter_map(|(id, [one, two])| if two.is_none() { Some((one.as_ref()?, *id)) } else { None }).collect::<Vec<_>>();
+        gold.sort_unstable();
+        silver.sort_unstable();
+
+        let mut lines = vec![target.render(Message::Results, &[("year", &year), ("day", &format!("{day:02}"))])];
+        if gold.is_empty() && silver.is_empty() { lines.push(target.render(Message::NoResults, &[])) }
+
+        let mut pos = 0;
+        for (two, one, id) in gold
+        {
+            let position = if id.spectator { String::new() } else { pos += 1; target.locale.ordinal(pos) };
+            lines.push(target.render(Message::ResultsGold, &[("position", &position),
+                                                              ("name",     &id.name),
+                                                              ("elapsed",  &Self::elapsed_since_unlock(year, day, two)?),
+                                                              ("delta",    &Self::clock(*two - *one))]));
+        }
+        if !silver.is_empty()
+        {
+            let names = silver.iter().map(|(_, id)| id.name.as_str()).collect::<Vec<_>>().join(", ");
+            lines.push(target.render(Message::ResultsSilver, &[("names", &names)]));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    // explain how each group of ranked participants with equal scores was ordered
+    // either by the first tie-breaker separating adjacent participants, or by sharing a position when none do
+    pub fn footnotes(ranking : &[Standing], target : &Target) -> FestiveResult<Vec<String>>
+    {
+        let ranked    = ranking.iter().filter(|s| !s.spectator).collect::<Vec<_>>();
+        let mut notes = Vec::new();
+        let mut ix    = 0;
+        while ix < ranked.len()
+        {
+            let shared = ranked[ix ..].iter().take_while(|s| s.position == ranked[ix].position).count();
+            let score  = target.locale.decimal(ranked[ix].score.to_f64().ok_or(FestiveError::Conv)?, 2);
+            if shared > 1
+            {
+                let names = target.locale.list(&ranked[ix .. ix + shared].iter().map(|s| s.name).collect::<Vec<_>>());
+                notes.push(target.render(Message::TieShared, &[("names", &names), ("position", &ranked[ix].position.unwrap_or_default()), ("score", &score)]));
+            }
+
+            let last = ranked[ix + shared - 1];
+            if let Some(next) = ranked.get(ix + shared).filter(|next| next.score == last.score)
+            {
+                let rule = target.tie_breakers.iter().find(|rule| !rule.compare(last, next).is_eq()).ok_or(FestiveError::Conv)?;
+                notes.push(target.render(Message::TieBroken, &[("higher", &last.name), ("lower", &next.name), ("score", &score), ("rule", &target.locale.tie_breaker(*rule))]));
+            }
+            ix += shared;
+        }
+        Ok(notes)
+    }
+
+    // the position, name, and score columns of the standings, padded to align, for each participant in the ranking
+    pub fn columns(ranking : &[Standing], locale : Locale) -> FestiveResult<Vec<String>>
+    {
+        // positions are only shown for the first of each group of participants sharing a position
+        let ordinals = ranking.iter()
+                              .enumerate()
+                              .map(|(ix, s)| s.position.filter(|&pos| ix == 0 || ranking[ix - 1].position != Some(pos)).map(|pos| locale.ordinal(pos)).unwrap_or_default())
+                              .collect::<Vec<_>>();
 
         // calculate width for positions
-        // the width of the maximum position to be displayed, plus one for ')'
-        let width_pos = 2 + (1 + distinct.iter()
-                                         .rev()
-                                         .skip(1)
-                                         .map(|grp| grp.len())
-                                         .sum::<usize>()).ilog10() as usize;
+        // the width of the widest position to be displayed
+        let width_pos = ordinals.iter().map(|o| o.chars().count()).max().unwrap_or(0);
 
         // calculate width for names
         // the length of the longest name, plus one for ':'
-        let width_name = 1 + scores.iter()
-                                   .map(|(id, _)| id.name.len()).max()
-                                   .unwrap_or(0);
+        let width_name = 1 + ranking.iter()
+                                    .map(|s| s.name.len()).max()
+                                    .unwrap_or(0);
 
         // calculate width for scores
-        // the width of the maximum score, formatted to two decimal places
+        // the width of the widest score, formatted to two decimal places
+        let width_score = ranking.iter()
+                                 .map(|s| locale.decimal(s.score.to_f64().unwrap_or(0.0), 2).chars().count())
+                                 .max()
+                                 .unwrap_or(0);
+
+        ranking.iter().zip(ordinals).map(|(standing, ordinal)|
+        {
+            Ok(format!("{:>width_pos$} {:<width_name$} {:>width_score$}",
+                       ordinal,
+                       format!("{}:", standing.name),
+                       locale.decimal(standing.score.to_f64().ok_or(FestiveError::Conv)?, 2)))
+        }).collect()
+    }
+
+    // team standings, aggregating the scores of each team's members, excluding spectators
+    // teams are made up of the configured members on the leaderboard, including those without any stars
+    // averages are taken over members with at least one star
+    pub fn team_standings(events : &[Event], config : &BTreeMap<u64, Member>, members : &BTreeMap<u64, String>, aggregate : Aggregate, locale : Locale) -> FestiveResult<String>
+    {
+        // score and star histogram per member, grouped by team
+        let mut teams : HashMap<&str, HashMap<u64, Histogram>> = HashMap::new();
+        for (id, member) in config.iter().filter(|(id, m)| !m.ignore && !m.spectator && members.contains_key(id))
+        {
+            let Some(team) = member.team.as_deref() else { continue };
+            teams.entry(team).or_default().insert(*id, (identities::zero(), HashMap::new()));
+        }
+        for e in events
+        {
+            let Some((score, stars)) = e.id.team.as_deref().and_then(|team| teams.get_mut(team)).and_then(|team| team.get_mut(&e.id.numeric)) else { continue };
+            *score                                 += e.score()?;
+            *stars.entry(e.day as u8).or_insert(0) += 1;
+        }
+
+        let mut scores = Vec::new();
+        for (team, members) in teams
+        {
+            // averages are over every member of the team, so members without stars lower their team's average
+            let mut member_scores = members.values().map(|(score, _)| score.clone()).collect::<Vec<_>>();
+            member_scores.sort_unstable_by(|a, b| b.cmp(a));
+            let total : BigRational = match aggregate
+            {
+                Aggregate::Sum     => member_scores.into_iter().sum(),
+                Aggregate::Top(n)  => member_scores.into_iter().take(n).sum(),
+                Aggregate::Average =>
+                {
+                    let count : BigRational = FromPrimitive::from_usize(member_scores.len()).ok_or(FestiveError::Conv)?;
+                    member_scores.into_iter().sum::<BigRational>() / count
+                }
+            };
+
+            // each day shows whether all members have both stars, or at least one member has a star
+            let grid = (1 ..= 25).map(|d|
+                                 {
+                                     let mut stars = members.values().map(|(_, stars)| *stars.get(&d).unwrap_or(&0));
+                                     if      stars.clone().all(|s| s == 2) { " ■" }
+                                     else if stars.any(|s| s > 0)          { " □" }
+                                     else                                  { " -" }
+                                 })
+                                 .chain(std::iter::once(" "))
+                                 .collect::<String>();
+            scores.push((team, members.len(), total, grid));
+        }
+
+        // sort by score descending, then by team name ascending
+        // group distinct scores
+        scores.sort_unstable_by(|(t1, _, s1, _), (t2, _, s2, _)| s2.cmp(s1).then(t1.cmp(t2)));
+        let distinct = scores.chunk_by(|a, b| a.2 == b.2).collect::<Vec<_>>();
+
+        // widths for positions, names, and scores, as for member standings
+        let ordinals    = distinct.iter().scan(1, |pos, grp| { let old = *pos; *pos += grp.len(); Some(locale.ordinal(old)) }).collect::<Vec<_>>();
+        let width_pos   = ordinals.iter().map(|o| o.chars().count()).max().unwrap_or(0);
+        let width_name  = 1 + scores.iter().map(|(team, ..)| team.len()).max().unwrap_or(0);
         let width_score = scores.iter()
-                                .map(|(_, (s, _))| s)
+                                .map(|(_, _, s, _)| locale.decimal(s.to_f64().unwrap_or(0.0), 2).chars().count())
                                 .max()
-                                .map(|s| 4 + s.to_f64().unwrap_or(0.0).log10().floor() as usize)
                                 .unwrap_or(0);
 
-        // generate standings report, with one line per participant
+        // generate team standings report, with one line per team
         let mut report = String::new();
-        for (pos, grp) in distinct.into_iter().scan(1, |pos, grp| { let old = *pos; *pos += grp.len(); Some((old, grp)) })
+        for (pos, grp) in ordinals.into_iter().zip(distinct)
         {
-            for (ix, (id, (score, stars))) in grp.iter().enumerate()
+            for (ix, (team, size, score, grid)) in grp.iter().enumerate()
             {
-                writeln!(&mut report, "{:>width_pos$} {:<width_name$} {:>width_score$.02}  [{}]",
-                                      if ix == 0 { format!("{pos})") } else { String::new() },
-                                      format!("{}:", id.name),
-                                      score.to_f64().ok_or(FestiveError::Conv)?,
-                                      (1 ..= 25).map(|d| match stars.get(&d).unwrap_or(&0) { 0 => " -", 1 => " □", _ => " ■" })
-                                                .chain(std::iter::once(" "))
-                                                .collect::<String>()
+                writeln!(&mut report, "{:>width_pos$} {:<width_name$} {:>width_score$}  [{grid}]  ({})",
+                                      if ix == 0 { pos.clone() } else { String::new() },
+                                      format!("{team}:"),
+                                      locale.decimal(score.to_f64().ok_or(FestiveError::Conv)?, 2),
+                                      locale.members(*size)
                         ).map_err(|_| FestiveError::Conv)?;
             }
         }
         Ok(report)
     }
 }
+
+// leaderboard fixtures shared with the tests of other modules
+#[cfg(test)]
+pub mod tests
+{
+    use super::*;
+
+    // a member's numeric ID, name, and (day, star, timestamp) completions
+    pub type Entry<'a> = (u64, Option<&'a str>, &'a [(u32, u8, i64)]);
+
+    // the timestamp of a number of seconds after a puzzle of 2025 unlocked
+    pub fn at(day : u32, secs : i64) -> i64
+    {
+        Event::puzzle_unlock(2025, day).unwrap().timestamp() + secs
+    }
+
+    // the events of a leaderboard without any configured members
+    pub fn events(members : &[Entry]) -> Vec<Event>
+    {
+        let (mut events, mut names) = (Vec::new(), BTreeMap::new());
+        Event::parse(&response(members), &BTreeMap::new(), &mut events, &mut names).unwrap();
+        events
+    }
+
+    // a leaderboard response for 2025
+    pub fn response(members : &[Entry]) -> String
+    {
+        let mut json = json::object!{ event: "2025", members: {} };
+        for (id, name, completions) in members
+        {
+            let mut days = JsonValue::new_object();
+            for (day, star, ts) in *completions { days[day.to_string()][star.to_string()] = json::object!{ get_star_ts: *ts } }
+            json["members"][id.to_string()] = json::object!{ name: *name, completion_day_level: days };
+        }
+        json.dump()
+    }
+
+    fn member(alias : Option<&str>) -> Member
+    {
+        Member { alias: alias.map(str::to_string), discord: None, ignore: false, spectator: false, team: None }
+    }
+
+    #[test]
+    fn aliases_are_not_recorded_in_the_members_snapshot()
+    {
+        let config                    = BTreeMap::from([(1, member(Some("Al")))]);
+        let (mut events, mut members) = (Vec::new(), BTreeMap::new());
+        Event::parse(&response(&[(1, Some("Alice"), &[(1, 1, 1764565200)]), (2, None, &[])]), &config, &mut events, &mut members).unwrap();
+
+        assert_eq!(members, BTreeMap::from([(1, "Alice".to_string()), (2, "anonymous user #2".to_string())]));
+        assert_eq!(events[0].name(false), "Al");
+    }
+
+    #[test]
+    fn ignored_members_are_kept_in_the_members_snapshot()
+    {
+        let config                    = BTreeMap::from([(2, Member { ignore: true, ..member(None) })]);
+        let (mut events, mut members) = (Vec::new(), BTreeMap::new());
+        Event::parse(&response(&[(1, Some("Alice"), &[(1, 1, 1764565200)]), (2, Some("Bob"), &[(1, 1, 1764565300)])]), &config, &mut events, &mut members).unwrap();
+
+        assert_eq!(members, BTreeMap::from([(1, "Alice".to_string()), (2, "Bob".to_string())]));
+        assert!(events.iter().all(|e| e.member() == 1));
+    }
+
+    #[test]
+    fn team_standings_include_members_without_stars()
+    {
+        let team   = |name : &str| Member { team: Some(name.to_string()), ..member(None) };
+        let config = BTreeMap::from([(1, team("red")), (2, team("red")), (3, team("blue")), (4, team("green"))]);
+        let (mut events, mut members) = (Vec::new(), BTreeMap::new());
+        Event::parse(&response(&[(1, Some("Alice"), &[(1, 1, 1764565200), (1, 2, 1764565300)]), (2, Some("Bob"), &[]), (3, Some("Carol"), &[])]), &config, &mut events, &mut members).unwrap();
+
+        let report = Event::team_standings(&events, &config, &members, Aggregate::Sum, Locale::En).unwrap();
+        let lines  = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("1) red:  2.00  [ □ -"));
+        assert!(lines[0].ends_with("(2 members)"));
+        assert!(lines[1].starts_with("2) blue: 0.00  [ - -"));
+        assert!(lines[1].ends_with("(1 member)"));
+
+        let average = Event::team_standings(&events, &config, &members, Aggregate::Average, Locale::En).unwrap();
+        assert!(average.lines().any(|line| line.starts_with("1) red:  1.00")));
+        assert!(average.lines().any(|line| line.starts_with("2) blue: 0.00")));
+    }
+
+    #[test]
+    fn ties_are_broken_in_turn_or_shared()
+    {
+        let events = events(&[(1, Some("Alice"), &[(1, 1, at(1, 60)), (1, 2, at(1, 600))]),
+                              (2, Some("Bob"),   &[(1, 1, at(1, 60)), (1, 2, at(1, 300))]),
+                              (3, Some("Carol"), &[(1, 1, at(1, 60)), (2, 1, at(2, 60))]),
+                              (4, Some("Dave"),  &[(1, 1, at(1, 60)), (1, 2, at(1, 600))]),
+                              (5, Some("Eve"),   &[(1, 1, at(1, 60))])]);
+        let target  = Target::from_json(r#"{ "tie_breakers": ["most_gold_stars", "earliest_final_star"] }"#);
+        let ranking = Event::ranking(&events, &target.tie_breakers, &DateTime::from_timestamp(at(3, 0), 0).unwrap()).unwrap();
+
+        let positions = ranking.iter().map(|s| (s.name, s.position)).collect::<Vec<_>>();
+        assert_eq!(positions, [("Bob", Some(1)), ("Alice", Some(2)), ("Dave", Some(2)), ("Carol", Some(4)), ("Eve", Some(5))]);
+        assert_eq!(Event::footnotes(&ranking, &target).unwrap(),
+                   ["Bob and Alice are tied on a score of 2.00, and are separated by earliest final star.",
+                    "Alice and Dave are tied on a score of 2.00, and share position 2.",
+                    "Dave and Carol are tied on a score of 2.00, and are separated by most gold stars."]);
+    }
+
+    #[test]
+    fn spectators_are_unranked_and_not_first_solvers()
+    {
+        let config = BTreeMap::from([(1, Member { spectator: true, ..member(None) })]);
+        let (mut events, mut members) = (Vec::new(), BTreeMap::new());
+        Event::parse(&response(&[(1, Some("Alice"), &[(1, 1, at(1, 60)), (1, 2, at(1, 120))]), (2, Some("Bob"), &[(1, 1, at(1, 300))])]), &config, &mut events, &mut members).unwrap();
+        let ranking = Event::ranking(&events, &[TieBreaker::Stars], &DateTime::from_timestamp(at(2, 0), 0).unwrap()).unwrap();
+
+        let ranked = ranking.iter().map(|s| (s.name, s.position, s.first_solves)).collect::<Vec<_>>();
+        assert_eq!(ranked, [("Bob", Some(1), 1), ("Alice", None, 0)]);
+    }
+
+    #[test]
+    fn digests_are_grouped_by_day_and_part()
+    {
+        let events = events(&[(1, Some("Alice"), &[(1, 1, at(1, 60)), (1, 2, at(1, 120)), (2, 1, at(2, 60))]), (2, Some("Bob"), &[(1, 1, at(1, 300))])]);
+        let digest = Event::digest(2025, events.iter(), &events, &Target::from_json("{}"), false).unwrap();
+        assert_eq!(digest, ":christmas_tree: [2025] Puzzle completions since the last update:\n\
+                            :star: Puzzle 01, part one: 🥇 Alice (1 point), Bob (1 point)\n\
+                            :star: :star: Puzzle 01, part two: 🥇 Alice (1 point)\n\
+                            :star: Puzzle 02, part one: 🥇 Alice (1 point)");
+    }
+
+    #[test]
+    fn digest_first_solves_are_among_all_events()
+    {
+        // Alice's earlier completion was announced in a previous digest
+        let events = events(&[(1, Some("Alice"), &[(1, 1, at(1, 60))]), (2, Some("Bob"), &[(1, 1, at(1, 300))])]);
+        let digest = Event::digest(2025, events.iter().filter(|e| e.member() == 2), &events, &Target::from_json("{}"), false).unwrap();
+        assert!(digest.ends_with(":star: Puzzle 01, part one: Bob (1 point)"));
+    }
+
+    #[test]
+    fn digests_are_routed_to_each_target()
+    {
+        let config = crate::config::tests::config(r#"{ "targets": { "gold": { "url": "https://example.com/gold" }, "silver": { "url": "https://example.com/silver" } },
+                                                       "routes":  { "part_one": ["notify", "silver"], "part_two": ["notify", "gold"] } }"#);
+        let events = events(&[(1, Some("Alice"), &[(1, 1, at(1, 60)), (1, 2, at(1, 120))]), (2, Some("Bob"), &[(1, 1, at(1, 300))])]);
+
+        let digests = Event::digests(2025, &events.iter().collect::<Vec<_>>(), &events, &config).unwrap();
+        let routes  = digests.iter().map(|(webhook, route, digest)| (webhook.name, *route, digest.lines().count() - 1)).collect::<Vec<_>>();
+        assert_eq!(routes, [("gold", Route::PartTwo, 1), ("notify", Route::PartTwo, 2), ("silver", Route::PartOne, 1)]);
+
+        // targets with none of the parts routed to them receive no digest
+        let silver = Event::digests(2025, &events.iter().filter(|e| e.star() == 1).collect::<Vec<_>>(), &events, &config).unwrap();
+        assert_eq!(silver.iter().map(|(webhook, route, _)| (webhook.name, *route)).collect::<Vec<_>>(), [("notify", Route::PartOne), ("silver", Route::PartOne)]);
+    }
+
+    #[test]
+    fn results_reject_invalid_stars()
+    {
+        let (mut events, mut members) = (Vec::new(), BTreeMap::new());
+        Event::parse(&response(&[(1, Some("Alice"), &[(1, 1, 1764565200), (1, 3, 1764565300)])]), &BTreeMap::new(), &mut events, &mut members).unwrap();
+        let target = Target::from_json("{}");
+        assert!(matches!(Event::results(2025, 1, &events, &target), Err(FestiveError::Parse)));
+        assert!(Event::results(2025, 2, &events, &target).is_ok());
+    }
+}
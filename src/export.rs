@@ -0,0 +1,324 @@
+use std::{ fmt::Write, path::Path };
+use json::{ object, JsonValue };
+use chrono::{ DateTime, Utc };
+use num_traits::ToPrimitive;
+use crate::{ chart, config::Target, event::{ Event, Standing }, html, image, template::Message, error::{ FestiveResult, FestiveError }};
+
+// renderings of the standings, attached to standings announcements or written to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Export { Text, Csv, Json, Html, Png, Chart }
+
+// standings included in the content of standings announcements, for targets which can't receive files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout { Markdown, Code }
+
+// an inline layout, optionally truncated to the top N participants
+#[derive(Debug, Clone, Copy)]
+pub struct Inline
+{
+    pub layout: Layout,
+    pub top:    Option<usize>
+}
+
+impl Export
+{
+    pub fn parse(name : &str) -> Option<Export>
+    {
+        match name
+        {
+            "text"  => Some(Export::Text),
+            "csv"   => Some(Export::Csv),
+            "json"  => Some(Export::Json),
+            "html"  => Some(Export::Html),
+            "png"   => Some(Export::Png),
+            "chart" => Some(Export::Chart),
+            _       => None
+        }
+    }
+
+    pub fn extension(self) -> &'static str
+    {
+        match self
+        {
+            Export::Text  => "txt",
+            Export::Csv   => "csv",
+            Export::Json  => "json",
+            Export::Html  => "html",
+            Export::Png   => "png",
+            Export::Chart => "svg"
+        }
+    }
+
+    // the text, HTML, PNG, and chart renderings are formatted for the target's locale, the others are locale-independent
+    // PNG renderings are unlocalised for locales the bundled font can't draw
+    pub fn render(self, year : i32, events : &[Event], current : &DateTime<Utc>, target : &Target) -> FestiveResult<Vec<u8>>
+    {
+        Ok(match self
+        {
+            Export::Text if events.is_empty() => (target.render(Message::NoScores, &[]) + "\n").into_bytes(),
+            Export::Text                      => Event::standings(events, current, target)?.into_bytes(),
+            Export::Csv                       => Self::csv(&Event::ranking(events, &target.tie_breakers, current)?)?.into_bytes(),
+            Export::Json                      => Self::json(year, &Event::ranking(events, &target.tie_breakers, current)?)?.into_bytes(),
+            Export::Html                      => html::page(year, &Event::ranking(events, &target.tie_breakers, current)?, target)?.into_bytes(),
+            Export::Png if events.is_empty()  => image::message(&target.templates.render(image::locale(target.locale)?, Message::NoScores, &[]))?,
+            Export::Png                       => image::standings(&Event::ranking(events, &target.tie_breakers, current)?, image::locale(target.locale)?)?,
+            Export::Chart                     => chart::scores(year, events, current, target)?.into_bytes()
+        })
+    }
+
+    // write each export to the directory, named by year and leaderboard, replacing previous exports
+    pub fn write(exports : &[Export], directory : &Path, year : i32, leaderboard : &str, events : &[Event], current : &DateTime<Utc>, target : &Target) -> FestiveResult<()>
+    {
+        std::fs::create_dir_all(directory).map_err(|_| FestiveError::File)?;
+        for export in exports
+        {
+            let path = directory.join(format!("standings_{year}_{leaderboard}.{}", export.extension()));
+            println!("writing {}", path.display());
+            std::fs::write(path, export.render(year, events, current, target)?).map_err(|_| FestiveError::File)?;
+        }
+        Ok(())
+    }
+
+    // one row per participant, with their star count for each day followed by the timestamp of each star
+    // spectators have an empty position
+    fn csv(ranking : &[Standing]) -> FestiveResult<String>
+    {
+        let mut header = ["position", "name", "member", "spectator", "team", "score", "score_float", "stars", "current_streak", "longest_streak", "first_solves"].map(str::to_string).to_vec();
+        header.extend((1 ..= 25).map(|day| format!("day_{day}")));
+        header.extend((1 ..= 25).flat_map(|day| [format!("day_{day}_part_1"), format!("day_{day}_part_2")]));
+
+        let mut rows = vec![header];
+        for standing in ranking
+        {
+            let mut row = vec![standing.position.map(|pos| pos.to_string()).unwrap_or_default(),
+                               standing.name.to_string(),
+                               standing.member.to_string(),
+                               standing.spectator.to_string(),
+                               standing.team.unwrap_or_default().to_string(),
+                               standing.score.to_string(),
+                               standing.score.to_f64().ok_or(FestiveError::Conv)?.to_string(),
+                               standing.completions.len().to_string(),
+                               standing.streak.current.to_string(),
+                               standing.streak.longest.to_string(),
+                               standing.first_solves.to_string()];
+            row.extend((1 ..= 25).map(|day| standing.stars(day).to_string()));
+            row.extend((1 ..= 25).flat_map(|day| [1, 2].map(|star| standing.completions.get(&(day, star)).map(|ts| ts.to_rfc3339()).unwrap_or_default())));
+            rows.push(row);
+        }
+
+        let mut csv = String::new();
+        for row in rows
+        {
+            writeln!(&mut csv, "{}", row.iter().map(|field| Self::quote(field)).collect::<Vec<_>>().join(",")).map_err(|_| FestiveError::Conv)?;
+        }
+        Ok(csv)
+    }
+
+    // every field is quoted, with quotes doubled, so commas, quotes, and line breaks stay within their fields
+    // names are controlled by members, so fields which spreadsheets would interpret as formulas are prefixed with an apostrophe
+    fn quote(field : &str) -> String
+    {
+        let prefix = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) { "'" } else { "" };
+        format!("\"{prefix}{}\"", field.replace('"', "\"\""))
+    }
+
+    // scores are given exactly as fractions, and approximately as floats
+    // days without any stars are omitted
+    fn json(year : i32, ranking : &[Standing]) -> FestiveResult<String>
+    {
+        let mut members = JsonValue::new_array();
+        for standing in ranking
+        {
+            let mut days = JsonValue::new_array();
+            for day in (1 ..= 25).filter(|&day| standing.stars(day) > 0)
+            {
+                let timestamp = |star| standing.completions.get(&(day, star)).map(|ts| ts.to_rfc3339());
+                days.push(object!{ day: day, stars: standing.stars(day), part_1: timestamp(1), part_2: timestamp(2) }).map_err(|_| FestiveError::Conv)?;
+            }
+
+            members.push(object!
+            {
+                position:       standing.position,
+                name:           standing.name,
+                member:         standing.member,
+                spectator:      standing.spectator,
+                team:           standing.team,
+                score:          standing.score.to_string(),
+                score_float:    standing.score.to_f64().ok_or(FestiveError::Conv)?,
+                stars:          standing.completions.len(),
+                current_streak: standing.streak.current,
+                longest_streak: standing.streak.longest,
+                first_solves:   standing.first_solves,
+                days:           days
+            }).map_err(|_| FestiveError::Conv)?;
+        }
+        Ok(object!{ year: year, members: members }.pretty(4))
+    }
+}
+
+impl Inline
+{
+    // the standings, truncated to as many participants as fit within the limit, in UTF-16 code units, if there is one
+    // the number of participants omitted is noted after the standings
+    pub fn render(self, events : &[Event], current : &DateTime<Utc>, target : &Target, limit : Option<usize>) -> FestiveResult<String>
+    {
+        if events.is_empty() { return Ok(target.render(Message::NoScores, &[])) }
+        let ranking = Event::ranking(events, &target.tie_breakers, current)?;
+
+        // a header and footer around one row per participant
+        let (header, rows, footer) = match self.layout
+        {
+            Layout::Markdown =>
+            {
+                let rows = ranking.iter().map(|standing| Ok(format!("| {} | {} | {} | {} | {} ({}) | {} |",
+                                                                   standing.position.map(|pos| pos.to_string()).unwrap_or_default(),
+                                                                   Self::escape(standing.name),
+                                                                   target.locale.decimal(standing.score.to_f64().ok_or(FestiveError::Conv)?, 2),
+                                                                   standing.completions.len(),
+                                                                   standing.streak.current,
+                                                                   standing.streak.longest,
+                                                                   standing.first_solves)))
+                                         .collect::<FestiveResult<Vec<_>>>()?;
+                ("| # | Name | Score | Stars | Streak | Firsts |\n|--:|:--|--:|--:|--:|--:|\n", rows, "")
+            },
+
+            // the text standings, with the line separating spectators kept with the first spectator
+            Layout::Code =>
+            {
+                let rows = ranking.iter()
+                                  .zip(Event::columns(&ranking, target.locale)?)
+                                  .enumerate()
+                                  .map(|(ix, (standing, columns))|
+                                  {
+                                      let separator = if standing.spectator && ix > 0 && !ranking[ix - 1].spectator { "\n" } else { "" };
+                                      format!("{separator}{columns}  {}{}", standing.grid(), standing.notes(target.locale).map(|s| format!("  {s}")).unwrap_or_default())
+                                  })
+                                  .collect();
+                ("```\n", rows, "\n```")
+            }
+        };
+
+        // the most participants fitting within the limit, or just the note if none fit
+        let mut shown = self.top.unwrap_or(usize::MAX).min(rows.len());
+        loop
+        {
+            let mut lines = Vec::new();
+            if shown > 0          { lines.push(format!("{header}{}{footer}", rows[.. shown].join("\n"))) }
+            if shown < rows.len() { lines.push(target.render(Message::Others, &[("count", &(rows.len() - shown))])) }
+            let content = lines.join("\n");
+            if shown == 0 || limit.is_none_or(|limit| content.encode_utf16().count() <= limit) { return Ok(content) }
+            shown -= 1;
+        }
+    }
+
+    // escape characters which have meaning in Markdown
+    fn escape(text : &str) -> String
+    {
+        text.chars().fold(String::new(), |mut escaped, c|
+        {
+            if "\\`*_~|<>[]".contains(c) { escaped.push('\\') }
+            escaped.push(c);
+            escaped
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::collections::BTreeMap;
+    use crate::{ config::Member, event::tests::{ at, events, response }};
+
+    fn render(export : Export, events : &[Event]) -> String
+    {
+        String::from_utf8(export.render(2025, events, &DateTime::from_timestamp(at(26, 0), 0).unwrap(), &Target::from_json("{}")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn csv_fields_are_quoted()
+    {
+        let csv  = render(Export::Csv, &events(&[(1, Some("Eve, \"the\"\nhacker"), &[(1, 1, at(1, 60))]), (2, Some("=HYPERLINK(\"x\")"), &[(1, 1, at(1, 120))])]));
+        let rows = csv.strip_suffix('\n').unwrap().split("\n\"").collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].starts_with("\"position\",\"name\",\"member\","));
+        assert!(rows[1].starts_with("1\",\"'=HYPERLINK(\"\"x\"\")\",\"2\","));
+        assert!(rows[2].starts_with("1\",\"Eve, \"\"the\"\"\nhacker\",\"1\",\"false\",\"\",\"1\",\"1\",\"1\","));
+        assert!(rows.iter().all(|row| row.split("\",\"").count() == 11 + 25 + 50));
+    }
+
+    #[test]
+    fn json_contains_each_participant()
+    {
+        let json = json::parse(&render(Export::Json, &events(&[(1, Some("Alice"), &[(1, 1, at(1, 60)), (1, 2, at(1, 120)), (3, 1, at(4, 0))]), (2, None, &[])]))).unwrap();
+        assert_eq!(json["year"], 2025);
+        assert_eq!(json["members"].len(), 1);
+
+        let alice = &json["members"][0];
+        assert_eq!(alice["position"], 1);
+        assert_eq!(alice["score"], "5/2");
+        assert_eq!(alice["stars"], 3);
+        assert_eq!(alice["days"].len(), 2);
+        assert_eq!(alice["days"][1]["day"], 3);
+        assert!(alice["days"][1]["part_2"].is_null());
+    }
+
+    // Alice, Bob, and Carol are ranked in that order, and Dave is a spectator
+    fn participants() -> Vec<Event>
+    {
+        let config = BTreeMap::from([(4, Member { alias: None, discord: None, ignore: false, spectator: true, team: None })]);
+        let (mut events, mut members) = (Vec::new(), BTreeMap::new());
+        Event::parse(&response(&[(1, Some("Alice"),  &[(1, 1, at(1, 60)), (1, 2, at(1, 120)), (2, 1, at(2, 60))]),
+                                 (2, Some("Bob"),    &[(1, 1, at(1, 300)), (1, 2, at(1, 600))]),
+                                 (3, Some("Carol|"), &[(1, 1, at(1, 900))]),
+                                 (4, Some("Dave"),   &[(1, 1, at(1, 30))])]), &config, &mut events, &mut members).unwrap();
+        events
+    }
+
+    fn inline(layout : Layout, top : Option<usize>, limit : Option<usize>) -> String
+    {
+        Inline { layout, top }.render(&participants(), &DateTime::from_timestamp(at(3, 0), 0).unwrap(), &Target::from_json("{}"), limit).unwrap()
+    }
+
+    #[test]
+    fn inline_standings_are_truncated_to_the_top_participants()
+    {
+        let markdown = inline(Layout::Markdown, Some(2), None);
+        let lines    = markdown.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[2].starts_with("| 1 | Alice | 3.00 | 3 |"));
+        assert!(lines[3].starts_with("| 2 | Bob | 2.00 | 2 |"));
+        assert_eq!(lines[4], "…and 2 others");
+
+        // names are escaped, and spectators have no position
+        let all = inline(Layout::Markdown, None, None);
+        assert!(all.contains("\n| 3 | Carol\\| | 1.00 | 1 |"));
+        assert!(all.ends_with("\n|  | Dave | 1.00 | 1 | 0 (0) | 0 |"));
+    }
+
+    #[test]
+    fn inline_standings_are_truncated_to_the_limit()
+    {
+        let full = inline(Layout::Code, None, None);
+        let cut  = inline(Layout::Code, None, Some(full.encode_utf16().count() - 1));
+        assert!(cut.encode_utf16().count() < full.encode_utf16().count());
+        assert!(cut.starts_with("```\n") && cut.ends_with("\n```\n…and 1 others"));
+        assert_eq!(cut.lines().filter(|line| line.contains('[')).count(), 3);
+
+        // when no participants fit, only the note is given
+        assert_eq!(inline(Layout::Code, None, Some(10)), "…and 4 others");
+    }
+
+    #[test]
+    fn code_blocks_separate_spectators_only_when_shown()
+    {
+        let all   = inline(Layout::Code, None, None);
+        let lines = all.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 7);
+        assert_eq!((lines[0], lines[4], lines[6]), ("```", "", "```"));
+        assert!(lines[5].contains("Dave"));
+
+        let ranked = inline(Layout::Code, Some(3), None);
+        assert!(ranked.contains("Carol|") && ranked.ends_with("]\n```\n…and 1 others"));
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt::Write;
+use num_traits::ToPrimitive;
+use crate::{ config::Target, event::{ Event, Standing }, error::{ FestiveResult, FestiveError }};
+
+// styling and sorting for the standings page, which is self-contained so it can be served as a single file
+const STYLE : &str = "body { background: #0f0f23; color: #cccccc; font-family: monospace; }
+table { border-collapse: collapse; }
+th { cursor: pointer; color: #00cc00; padding: 0 0.5em; }
+td { padding: 0 0.5em; text-align: right; }
+td.name { text-align: left; }
+td.day { padding: 0; width: 1.2em; text-align: center; }
+td.gold { color: #ffff66; }
+td.silver { color: #9999cc; }
+td.none { color: #333340; }
+tr.spectator { font-style: italic; }";
+
+const SCRIPT : &str = "function sort(column) {
+  const body = document.getElementById('standings').tBodies[0];
+  const rows = Array.from(body.rows);
+  const key  = row => row.cells[column].dataset.sort;
+  const desc = body.dataset.column == column && body.dataset.order != 'desc';
+  rows.sort((a, b) => {
+    const [x, y] = [key(a), key(b)];
+    const order  = isNaN(x) || isNaN(y) ? x.localeCompare(y) : x - y;
+    return desc ? -order : order;
+  });
+  body.dataset.column = column;
+  body.dataset.order  = desc ? 'desc' : 'asc';
+  rows.forEach(row => body.appendChild(row));
+}";
+
+// standings page with a sortable table, and a star grid showing solve times on hover
+// the streak column sorts by current streak, with the longest streak in parentheses
+pub fn page(year : i32, ranking : &[Standing], target : &Target) -> FestiveResult<String>
+{
+    let mut html = String::new();
+    let title    = format!("Advent of Code {year}");
+    write!(&mut html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{STYLE}\n</style>\n<script>\n{SCRIPT}\n</script>\n</head>\n<body>\n<h1>{title}</h1>\n")
+        .map_err(|_| FestiveError::Conv)?;
+
+    // header cells sort by their column when clicked
+    html.push_str("<table id=\"standings\">\n<thead>\n<tr><th onclick=\"sort(0)\">#</th><th onclick=\"sort(1)\">Name</th><th onclick=\"sort(2)\">Score</th><th onclick=\"sort(3)\">Stars</th><th onclick=\"sort(4)\">Streak</th><th onclick=\"sort(5)\">Firsts</th>");
+    for day in 1 ..= 25 { write!(&mut html, "<th onclick=\"sort({})\">{day}</th>", day + 5).map_err(|_| FestiveError::Conv)? }
+    html.push_str("</tr>\n</thead>\n<tbody>\n");
+
+    // spectators have no position, so are sorted after ranked participants
+    for standing in ranking
+    {
+        let score = standing.score.to_f64().ok_or(FestiveError::Conv)?;
+        write!(&mut html, "<tr{}><td data-sort=\"{}\">{}</td><td class=\"name\" data-sort=\"{}\">{}</td><td data-sort=\"{score}\">{}</td><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{} ({})</td><td data-sort=\"{}\">{}</td>",
+                          if standing.spectator { " class=\"spectator\"" } else { "" },
+                          standing.position.unwrap_or(usize::MAX),
+                          standing.position.map(|pos| pos.to_string()).unwrap_or_default(),
+                          escape(standing.name),
+                          escape(standing.name),
+                          target.locale.decimal(score, 2),
+                          standing.completions.len(),
+                          standing.completions.len(),
+                          standing.streak.current,
+                          standing.streak.current,
+                          standing.streak.longest,
+                          standing.first_solves,
+                          standing.first_solves).map_err(|_| FestiveError::Conv)?;
+
+        for day in 1 ..= 25
+        {
+            // solve times since the puzzle unlocked, with one star for each part
+            let mut times = vec![format!("{day}")];
+            for (star, stars) in [(1, "★"), (2, "★★")]
+            {
+                if let Some(ts) = standing.completions.get(&(day, star))
+                {
+                    times.push(format!("{stars} {}", Event::elapsed_since_unlock(year, day, ts)?));
+                }
+            }
+            let (class, cell) = match standing.stars(day) { 0 => ("none", "-"), 1 => ("silver", "★"), _ => ("gold", "★") };
+            write!(&mut html, "<td class=\"day {class}\" data-sort=\"{}\" title=\"{}\">{cell}</td>", standing.stars(day), escape(&times.join("\n")))
+                .map_err(|_| FestiveError::Conv)?;
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    Ok(html)
+}
+
+// escape text for use in HTML content and attribute values
+pub fn escape(text : &str) -> String
+{
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn markup_is_escaped()
+    {
+        assert_eq!(escape(r#"<b>Tom & "Jerry's"</b>"#), "&lt;b&gt;Tom &amp; &quot;Jerry&#39;s&quot;&lt;/b&gt;");
+    }
+}
@@ -0,0 +1,187 @@
+use ab_glyph::{ Font, FontRef, PxScale, ScaleFont, point };
+use crate::{ event::{ Event, Standing }, locale::Locale, template::Message, error::{ FestiveResult, FestiveError }};
+
+// the bundled font, so that rendering doesn't depend on the fonts installed on the system
+const FONT : &[u8] = include_bytes!("../assets/DejaVuSansMono.ttf");
+
+// font size and margin around the image, in pixels
+const SCALE  : f32 = 20.0;
+const MARGIN : usize = 12;
+
+// colours matching the AoC website
+const BACKGROUND : [u8 ; 3] = [0x0f, 0x0f, 0x23];
+const TEXT       : [u8 ; 3] = [0xcc, 0xcc, 0xcc];
+const GOLD       : [u8 ; 3] = [0xff, 0xff, 0x66];
+const SILVER     : [u8 ; 3] = [0x99, 0x99, 0xcc];
+const NONE       : [u8 ; 3] = [0x33, 0x33, 0x40];
+
+// an RGB image being drawn on, with text laid out on a grid of monospace character cells
+struct Canvas<'a>
+{
+    font:    ab_glyph::PxScaleFont<&'a FontRef<'a>>,
+    advance: f32,
+    line:    f32,
+    width:   usize,
+    height:  usize,
+    pixels:  Vec<u8>
+}
+
+// the standings as a PNG image, with the same columns as the text standings and a coloured cell for each day, followed by any streaks and first solves
+pub fn standings(ranking : &[Standing], locale : Locale) -> FestiveResult<Vec<u8>>
+{
+    // spectators are listed separately after the ranked participants, as in the text standings
+    let mut rows = Vec::new();
+    for (ix, (standing, columns)) in ranking.iter().zip(Event::columns(ranking, locale)?).enumerate()
+    {
+        if standing.spectator && ix > 0 && !ranking[ix - 1].spectator { rows.push(None) }
+        rows.push(Some((columns, standing)));
+    }
+
+    // each day's cell is two characters wide, following two characters of padding after the columns
+    let font       = FontRef::try_from_slice(FONT).map_err(|_| FestiveError::Conv)?;
+    let width      = rows.iter().flatten().map(|(columns, s)| columns.chars().count() + 2 + 2 * 25 + s.notes(locale).map_or(0, |s| 2 + s.chars().count())).max().unwrap_or(0);
+    let mut canvas = Canvas::new(&font, width, rows.len());
+    for (row, columns) in rows.iter().enumerate()
+    {
+        let Some((columns, standing)) = columns else { continue };
+        canvas.text(0, row, columns, TEXT);
+
+        let offset = columns.chars().count() + 2;
+        for day in 1 ..= 25
+        {
+            let colour = match standing.stars(day) { 0 => NONE, 1 => SILVER, _ => GOLD };
+            canvas.cell(offset + 2 * (day as usize - 1) + 1, row, colour);
+        }
+        if let Some(notes) = standing.notes(locale) { canvas.text(offset + 2 * 25 + 2, row, &notes, TEXT) }
+    }
+    canvas.encode()
+}
+
+// the bundled font has no CJK glyphs, so images for locales whose text it can't draw are unlocalised
+pub fn locale(locale : Locale) -> FestiveResult<Locale>
+{
+    let font   = FontRef::try_from_slice(FONT).map_err(|_| FestiveError::Conv)?;
+    let sample = [locale.ordinal(1), locale.streak(1, 1), locale.first_solves(1), locale.template(Message::NoScores).to_string()].concat();
+    Ok(if sample.chars().all(|c| c.is_whitespace() || font.glyph_id(c).0 != 0) { locale } else { Locale::En })
+}
+
+// a message as a PNG image, used in place of the standings when there are none
+pub fn message(message : &str) -> FestiveResult<Vec<u8>>
+{
+    let font       = FontRef::try_from_slice(FONT).map_err(|_| FestiveError::Conv)?;
+    let mut canvas = Canvas::new(&font, message.lines().map(|l| l.chars().count()).max().unwrap_or(0), message.lines().count());
+    for (row, line) in message.lines().enumerate()
+    {
+        canvas.text(0, row, line, TEXT);
+    }
+    canvas.encode()
+}
+
+impl<'a> Canvas<'a>
+{
+    // a blank canvas large enough for the given number of character columns and rows
+    fn new(font : &'a FontRef<'a>, columns : usize, rows : usize) -> Canvas<'a>
+    {
+        let font    = font.as_scaled(PxScale::from(SCALE));
+        let advance = font.h_advance(font.glyph_id('0'));
+        let line    = (font.height() + font.line_gap()).ceil();
+        let width   = 2 * MARGIN + (columns as f32 * advance).ceil() as usize;
+        let height  = 2 * MARGIN + (rows    as f32 * line).ceil()    as usize;
+        Canvas { font, advance, line, width, height, pixels: BACKGROUND.repeat(width * height) }
+    }
+
+    // pixel position of the top-left corner of a character cell
+    fn position(&self, column : usize, row : usize) -> (f32, f32)
+    {
+        (MARGIN as f32 + column as f32 * self.advance, MARGIN as f32 + row as f32 * self.line)
+    }
+
+    // blend a colour into a pixel with the given coverage, ignoring pixels outside of the canvas
+    fn blend(&mut self, x : usize, y : usize, colour : [u8 ; 3], coverage : f32)
+    {
+        if x >= self.width || y >= self.height { return }
+        let ix = 3 * (y * self.width + x);
+        for (pixel, c) in self.pixels[ix .. ix + 3].iter_mut().zip(colour)
+        {
+            *pixel = (*pixel as f32 * (1.0 - coverage) + c as f32 * coverage).round() as u8;
+        }
+    }
+
+    // draw a line of text starting at a character cell
+    fn text(&mut self, column : usize, row : usize, text : &str, colour : [u8 ; 3])
+    {
+        let (x, y) = self.position(column, row);
+        for (ix, c) in text.chars().enumerate()
+        {
+            let glyph = self.font.glyph_id(c).with_scale_and_position(self.font.scale(), point(x + ix as f32 * self.advance, y + self.font.ascent()));
+            if let Some(outline) = self.font.outline_glyph(glyph)
+            {
+                let bounds = outline.px_bounds();
+                let mut coverage = Vec::new();
+                outline.draw(|gx, gy, c| coverage.push((bounds.min.x as i64 + gx as i64, bounds.min.y as i64 + gy as i64, c)));
+                for (px, py, c) in coverage
+                {
+                    if px >= 0 && py >= 0 { self.blend(px as usize, py as usize, colour, c.min(1.0)) }
+                }
+            }
+        }
+    }
+
+    // fill a square in the centre of a character cell, one advance wide
+    fn cell(&mut self, column : usize, row : usize, colour : [u8 ; 3])
+    {
+        let (x, y) = self.position(column, row);
+        let size   = self.advance.floor() as usize;
+        let top    = (y + (self.line - size as f32) / 2.0).round() as usize;
+        for py in top .. top + size
+        {
+            for px in x.round() as usize .. x.round() as usize + size
+            {
+                self.blend(px, py, colour, 1.0);
+            }
+        }
+    }
+
+    fn encode(self) -> FestiveResult<Vec<u8>>
+    {
+        let mut bytes   = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|_| FestiveError::Conv)?;
+        writer.write_image_data(&self.pixels).map_err(|_| FestiveError::Conv)?;
+        writer.finish().map_err(|_| FestiveError::Conv)?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use chrono::DateTime;
+    use crate::{ config::Target, export::Export, event::tests::{ at, events }};
+
+    #[test]
+    fn locales_the_font_cant_draw_are_unlocalised()
+    {
+        assert_eq!(locale(Locale::En).unwrap(), Locale::En);
+        assert_eq!(locale(Locale::Fr).unwrap(), Locale::Fr);
+        assert_eq!(locale(Locale::De).unwrap(), Locale::De);
+        assert_eq!(locale(Locale::Ja).unwrap(), Locale::En);
+    }
+
+    #[test]
+    fn ja_standings_images_are_rendered_in_english()
+    {
+        let current = DateTime::from_timestamp(at(3, 0), 0).unwrap();
+        let render  = |events : &[Event], settings : &str| Export::Png.render(2025, events, &current, &Target::from_json(settings)).unwrap();
+
+        let events = events(&[(1, Some("Alice"), &[(1, 1, at(1, 60)), (1, 2, at(1, 120)), (2, 1, at(2, 60))]), (2, Some("Bob"), &[(1, 1, at(1, 300))])]);
+        let ja     = render(&events, r#"{ "locale": "ja" }"#);
+        assert!(ja.starts_with(b"\x89PNG"));
+        assert_eq!(ja, render(&events, "{}"));
+        assert_eq!(render(&[], r#"{ "locale": "ja" }"#), render(&[], "{}"));
+    }
+}
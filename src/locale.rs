@@ -0,0 +1,461 @@
+use chrono::{ DateTime, Duration, TimeZone };
+use num_rational::BigRational;
+use num_traits::identities;
+use crate::{ achievements::Achievement, event::TieBreaker, template::Message };
+
+// built-in language packs for messages, and the formatting of numbers, positions, and timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Locale { En, Fr, De, Ja }
+
+impl Locale
+{
+    pub fn iter() -> impl Iterator<Item = Locale>
+    {
+        [Locale::En, Locale::Fr, Locale::De, Locale::Ja].into_iter()
+    }
+
+    pub fn parse(code : &str) -> Option<Locale>
+    {
+        match code
+        {
+            "en" => Some(Locale::En),
+            "fr" => Some(Locale::Fr),
+            "de" => Some(Locale::De),
+            "ja" => Some(Locale::Ja),
+            _    => None
+        }
+    }
+
+    // built-in template for a message in this locale
+    pub fn template(self, message : Message) -> &'static str
+    {
+        match self
+        {
+            Locale::En => match message
+            {
+                Message::Initialising      => "🦀 Festive Bot v{version} is initialising...",
+                Message::Initialised       => "🦀 Initialisation successful!",
+                Message::Terminating       => "🦀 Received termination signal, exiting!",
+                Message::Fatal             => "⚠ Festive Bot experienced an unrecoverable error, exiting!",
+                Message::Error             => "⚠ Error: {error}",
+                Message::Reloaded          => "🦀 Configuration reloaded!",
+                Message::ReloadFailed      => "⚠ Failed to reload configuration, keeping the previous configuration: {error}",
+                Message::Idle              => "🦀 Outside the Advent of Code season, idling until {until}. See you in December! 👋",
+                Message::Heartbeat         => "🦀 Heartbeat {timestamp}",
+                Message::AddingYear        => "🦀 Adding {year} to live years!",
+                Message::Countdown         => "🎄 [{year}] Advent of Code begins in {countdown}! ⏳",
+                Message::Completion        => ":christmas_tree: [{year}] {name} has completed puzzle {day}, part {part}, scoring {points}! {emoji}",
+                Message::DigestHeader      => ":christmas_tree: [{year}] Puzzle completions since the last update:",
+                Message::DigestLine        => "{emoji} Puzzle {day}, part {part}: {entries}",
+                Message::DigestEntry       => "{name} ({points})",
+                Message::Join              => ":wave: [{year}] Welcome to the leaderboard, {name}! They have {stars} so far. :star:",
+                Message::Leave             => ":wave: [{year}] {name} has left the leaderboard.",
+                Message::Rename            => ":label: [{year}] {old} is now known as {new}.",
+                Message::Live              => "🎄 [{year}] Advent of Code is now live! 🎉",
+                Message::Unlock            => "🎄 [{year}] Puzzle {day} is now unlocked! 🔓",
+                Message::Standings         => "🎄 [{year}] Current Standings 🏆",
+                Message::TeamStandings     => "🎄 [{year}] Current Team Standings 🏆",
+                Message::NoScores          => "No scores yet: get programming!",
+                Message::NoTeamScores      => "No team scores yet: get programming!",
+                Message::SignOff           => "🎄 [{year}] Festive Bot signing off. Happy New Year! 👋",
+                Message::Held              => "🌙 Messages held during quiet hours:",
+                Message::Others            => "…and {count} others",
+                Message::TieBroken         => "{higher} and {lower} are tied on a score of {score}, and are separated by {rule}.",
+                Message::TieShared         => "{names} are tied on a score of {score}, and share position {position}.",
+                Message::Results           => "🎄 [{year}] Results for puzzle {day} 📋",
+                Message::ResultsGold       => "{position} {name}: {elapsed} (part two +{delta})",
+                Message::ResultsSilver     => ":star: One star so far: {names}",
+                Message::NoResults         => "No one has completed this puzzle yet.",
+                Message::Achievement       => "🏅 [{year}] {name} earned the {achievement} achievement!",
+                Message::AchievementsRecap => "🏅 Achievements earned in {year}:",
+                Message::AchievementsLine  => "{name}: {achievements}",
+                Message::StreakExtended    => "🔥 [{year}] {name} has completed {days} puzzles in a row on their release day!",
+                Message::StreakBroken      => "🧊 [{year}] {name}'s streak of {days} puzzles completed on their release day has ended.",
+                Message::FirstCompletion   => "🥇 [{year}] {name} is the first to solve puzzle {day}, part {part}, scoring {points}! {emoji}",
+                Message::DigestFirst       => "🥇 {name} ({points})"
+            },
+
+            Locale::Fr => match message
+            {
+                Message::Initialising      => "🦀 Festive Bot v{version} est en cours d'initialisation...",
+                Message::Initialised       => "🦀 Initialisation réussie !",
+                Message::Terminating       => "🦀 Signal d'arrêt reçu, fermeture !",
+                Message::Fatal             => "⚠ Festive Bot a rencontré une erreur irrécupérable, fermeture !",
+                Message::Error             => "⚠ Erreur : {error}",
+                Message::Reloaded          => "🦀 Configuration rechargée !",
+                Message::ReloadFailed      => "⚠ Échec du rechargement de la configuration, la configuration précédente est conservée : {error}",
+                Message::Idle              => "🦀 Hors de la saison de l'Advent of Code, en veille jusqu'au {until}. Rendez-vous en décembre ! 👋",
+                Message::Heartbeat         => "🦀 Signe de vie {timestamp}",
+                Message::AddingYear        => "🦀 Ajout de {year} aux années actives !",
+                Message::Countdown         => "🎄 [{year}] L'Advent of Code commence dans {countdown} ! ⏳",
+                Message::Completion        => ":christmas_tree: [{year}] {name} a terminé l'énigme {day}, partie {part}, et marque {points} ! {emoji}",
+                Message::DigestHeader      => ":christmas_tree: [{year}] Énigmes terminées depuis la dernière mise à jour :",
+                Message::DigestLine        => "{emoji} Énigme {day}, partie {part} : {entries}",
+                Message::DigestEntry       => "{name} ({points})",
+                Message::Join              => ":wave: [{year}] Bienvenue dans le classement, {name} ! Déjà {stars}. :star:",
+                Message::Leave             => ":wave: [{year}] {name} a quitté le classement.",
+                Message::Rename            => ":label: [{year}] {old} s'appelle désormais {new}.",
+                Message::Live              => "🎄 [{year}] L'Advent of Code est lancé ! 🎉",
+                Message::Unlock            => "🎄 [{year}] L'énigme {day} est maintenant débloquée ! 🔓",
+                Message::Standings         => "🎄 [{year}] Classement actuel 🏆",
+                Message::TeamStandings     => "🎄 [{year}] Classement actuel des équipes 🏆",
+                Message::NoScores          => "Aucun score pour l'instant : à vos claviers !",
+                Message::NoTeamScores      => "Aucun score d'équipe pour l'instant : à vos claviers !",
+                Message::SignOff           => "🎄 [{year}] Festive Bot se déconnecte. Bonne année ! 👋",
+                Message::Held              => "🌙 Messages retenus pendant les heures calmes :",
+                Message::Others            => "… et {count} autres",
+                Message::TieBroken         => "{higher} et {lower} sont à égalité avec un score de {score}, et sont départagés par {rule}.",
+                Message::TieShared         => "{names} sont à égalité avec un score de {score}, et partagent la position {position}.",
+                Message::Results           => "🎄 [{year}] Résultats de l'énigme {day} 📋",
+                Message::ResultsGold       => "{position} {name} : {elapsed} (partie deux +{delta})",
+                Message::ResultsSilver     => ":star: Une étoile pour l'instant : {names}",
+                Message::NoResults         => "Personne n'a encore résolu cette énigme.",
+                Message::Achievement       => "🏅 [{year}] {name} a obtenu le succès « {achievement} » !",
+                Message::AchievementsRecap => "🏅 Succès obtenus en {year} :",
+                Message::AchievementsLine  => "{name} : {achievements}",
+                Message::StreakExtended    => "🔥 [{year}] {name} a résolu {days} énigmes d'affilée le jour de leur sortie !",
+                Message::StreakBroken      => "🧊 [{year}] La série de {name}, {days} énigmes résolues le jour de leur sortie, est terminée.",
+                Message::FirstCompletion   => "🥇 [{year}] {name} est le premier à résoudre l'énigme {day}, partie {part}, et marque {points} ! {emoji}",
+                Message::DigestFirst       => "🥇 {name} ({points})"
+            },
+
+            Locale::De => match message
+            {
+                Message::Initialising      => "🦀 Festive Bot v{version} wird initialisiert...",
+                Message::Initialised       => "🦀 Initialisierung erfolgreich!",
+                Message::Terminating       => "🦀 Beendigungssignal empfangen, wird beendet!",
+                Message::Fatal             => "⚠ Festive Bot hatte einen nicht behebbaren Fehler und wird beendet!",
+                Message::Error             => "⚠ Fehler: {error}",
+                Message::Reloaded          => "🦀 Konfiguration neu geladen!",
+                Message::ReloadFailed      => "⚠ Neuladen der Konfiguration fehlgeschlagen, die vorherige Konfiguration wird beibehalten: {error}",
+                Message::Idle              => "🦀 Außerhalb der Advent-of-Code-Saison, Ruhezustand bis {until}. Bis Dezember! 👋",
+                Message::Heartbeat         => "🦀 Lebenszeichen {timestamp}",
+                Message::AddingYear        => "🦀 {year} wird zu den aktiven Jahren hinzugefügt!",
+                Message::Countdown         => "🎄 [{year}] Advent of Code beginnt in {countdown}! ⏳",
+                Message::Completion        => ":christmas_tree: [{year}] {name} hat Rätsel {day}, Teil {part} gelöst und erhält {points}! {emoji}",
+                Message::DigestHeader      => ":christmas_tree: [{year}] Gelöste Rätsel seit der letzten Aktualisierung:",
+                Message::DigestLine        => "{emoji} Rätsel {day}, Teil {part}: {entries}",
+                Message::DigestEntry       => "{name} ({points})",
+                Message::Join              => ":wave: [{year}] Willkommen in der Rangliste, {name}! Bisher {stars}. :star:",
+                Message::Leave             => ":wave: [{year}] {name} hat die Rangliste verlassen.",
+                Message::Rename            => ":label: [{year}] {old} heißt jetzt {new}.",
+                Message::Live              => "🎄 [{year}] Advent of Code ist gestartet! 🎉",
+                Message::Unlock            => "🎄 [{year}] Rätsel {day} ist jetzt freigeschaltet! 🔓",
+                Message::Standings         => "🎄 [{year}] Aktueller Punktestand 🏆",
+                Message::TeamStandings     => "🎄 [{year}] Aktueller Punktestand der Teams 🏆",
+                Message::NoScores          => "Noch keine Punkte: ran an die Tastatur!",
+                Message::NoTeamScores      => "Noch keine Teampunkte: ran an die Tastatur!",
+                Message::SignOff           => "🎄 [{year}] Festive Bot verabschiedet sich. Frohes neues Jahr! 👋",
+                Message::Held              => "🌙 Während der Ruhezeit zurückgehaltene Nachrichten:",
+                Message::Others            => "… und {count} weitere",
+                Message::TieBroken         => "{higher} und {lower} haben jeweils {score} Punkte und werden nach „{rule}“ getrennt.",
+                Message::TieShared         => "{names} haben jeweils {score} Punkte und teilen sich Platz {position}.",
+                Message::Results           => "🎄 [{year}] Ergebnisse für Rätsel {day} 📋",
+                Message::ResultsGold       => "{position} {name}: {elapsed} (Teil zwei +{delta})",
+                Message::ResultsSilver     => ":star: Bisher ein Stern: {names}",
+                Message::NoResults         => "Noch niemand hat dieses Rätsel gelöst.",
+                Message::Achievement       => "🏅 [{year}] {name} hat die Errungenschaft „{achievement}“ erhalten!",
+                Message::AchievementsRecap => "🏅 Errungenschaften {year}:",
+                Message::AchievementsLine  => "{name}: {achievements}",
+                Message::StreakExtended    => "🔥 [{year}] {name} hat {days} Rätsel in Folge am Tag ihrer Veröffentlichung gelöst!",
+                Message::StreakBroken      => "🧊 [{year}] Die Serie von {name} mit {days} am Tag ihrer Veröffentlichung gelösten Rätseln ist beendet.",
+                Message::FirstCompletion   => "🥇 [{year}] {name} hat Rätsel {day}, Teil {part} als Erstes gelöst und erhält {points}! {emoji}",
+                Message::DigestFirst       => "🥇 {name} ({points})"
+            },
+
+            Locale::Ja => match message
+            {
+                Message::Initialising      => "🦀 Festive Bot v{version} を初期化しています...",
+                Message::Initialised       => "🦀 初期化に成功しました！",
+                Message::Terminating       => "🦀 終了シグナルを受信しました。終了します！",
+                Message::Fatal             => "⚠ Festive Bot で回復不能なエラーが発生しました。終了します！",
+                Message::Error             => "⚠ エラー: {error}",
+                Message::Reloaded          => "🦀 設定を再読み込みしました！",
+                Message::ReloadFailed      => "⚠ 設定の再読み込みに失敗しました。以前の設定を引き続き使用します: {error}",
+                Message::Idle              => "🦀 Advent of Code のシーズン外のため、{until} まで待機します。12月にまた会いましょう！ 👋",
+                Message::Heartbeat         => "🦀 ハートビート {timestamp}",
+                Message::AddingYear        => "🦀 {year}年を有効な年に追加します！",
+                Message::Countdown         => "🎄 [{year}] Advent of Code 開始まであと{countdown}！ ⏳",
+                Message::Completion        => ":christmas_tree: [{year}] {name} さんがパズル {day} のパート{part}を完了し、{points}を獲得しました！ {emoji}",
+                Message::DigestHeader      => ":christmas_tree: [{year}] 前回の更新以降に完了したパズル:",
+                Message::DigestLine        => "{emoji} パズル {day} パート{part}: {entries}",
+                Message::DigestEntry       => "{name} ({points})",
+                Message::Join              => ":wave: [{year}] {name} さん、リーダーボードへようこそ！ これまでの星は{stars}です。 :star:",
+                Message::Leave             => ":wave: [{year}] {name} さんがリーダーボードを離れました。",
+                Message::Rename            => ":label: [{year}] {old} さんの名前が {new} に変わりました。",
+                Message::Live              => "🎄 [{year}] Advent of Code が始まりました！ 🎉",
+                Message::Unlock            => "🎄 [{year}] パズル {day} が公開されました！ 🔓",
+                Message::Standings         => "🎄 [{year}] 現在の順位 🏆",
+                Message::TeamStandings     => "🎄 [{year}] 現在のチーム順位 🏆",
+                Message::NoScores          => "まだスコアはありません。プログラミングを始めましょう！",
+                Message::NoTeamScores      => "まだチームのスコアはありません。プログラミングを始めましょう！",
+                Message::SignOff           => "🎄 [{year}] Festive Bot はこれで失礼します。良いお年を！ 👋",
+                Message::Held              => "🌙 おやすみ時間中に保留されたメッセージ:",
+                Message::Others            => "…ほか{count}名",
+                Message::TieBroken         => "{higher} さんと {lower} さんはスコア {score} で同点ですが、{rule}で順位が決まりました。",
+                Message::TieShared         => "{names} さんはスコア {score} で同点のため、{position}位を分け合います。",
+                Message::Results           => "🎄 [{year}] パズル {day} の結果 📋",
+                Message::ResultsGold       => "{position} {name}: {elapsed} (パート2 +{delta})",
+                Message::ResultsSilver     => ":star: 星1つ: {names}",
+                Message::NoResults         => "このパズルを完了した人はまだいません。",
+                Message::Achievement       => "🏅 [{year}] {name} さんが実績「{achievement}」を獲得しました！",
+                Message::AchievementsRecap => "🏅 {year}年に獲得した実績:",
+                Message::AchievementsLine  => "{name}: {achievements}",
+                Message::StreakExtended    => "🔥 [{year}] {name} さんが{days}日連続で公開当日にパズルを解きました！",
+                Message::StreakBroken      => "🧊 [{year}] {name} さんの{days}日連続の公開当日解答記録が途切れました。",
+                Message::FirstCompletion   => "🥇 [{year}] {name} さんがパズル {day} のパート{part}を一番乗りで完了し、{points}を獲得しました！ {emoji}",
+                Message::DigestFirst       => "🥇 {name} ({points})"
+            }
+        }
+    }
+
+    // name of each part of a puzzle
+    pub fn part(self, star : u8) -> &'static str
+    {
+        match (self, star)
+        {
+            (Locale::En, 1) => "one",
+            (Locale::En, _) => "two",
+            (Locale::Fr, 1) => "un",
+            (Locale::Fr, _) => "deux",
+            (Locale::De, 1) => "eins",
+            (Locale::De, _) => "zwei",
+            (Locale::Ja, 1) => "1",
+            (Locale::Ja, _) => "2"
+        }
+    }
+
+    // scores are rational, so are written as fractions rather than decimals
+    pub fn points(self, score : &BigRational) -> String
+    {
+        let one = identities::one::<BigRational>();
+        match self
+        {
+            Locale::En => format!("{score} point{}",  if *score == one { "" } else { "s" }),
+            Locale::Fr => format!("{score} point{}",  if *score >  one { "s" } else { "" }),
+            Locale::De => format!("{score} Punkt{}",  if *score == one { "" } else { "e" }),
+            Locale::Ja => format!("{score}ポイント")
+        }
+    }
+
+    pub fn stars(self, count : usize) -> String
+    {
+        match self
+        {
+            Locale::En => format!("{count} star{}",   if count == 1 { "" } else { "s" }),
+            Locale::Fr => format!("{count} étoile{}", if count >  1 { "s" } else { "" }),
+            Locale::De => format!("{count} Stern{}",  if count == 1 { "" } else { "e" }),
+            Locale::Ja => format!("{count}個")
+        }
+    }
+
+    pub fn members(self, count : usize) -> String
+    {
+        match self
+        {
+            Locale::En => format!("{count} member{}", if count == 1 { "" } else { "s" }),
+            Locale::Fr => format!("{count} membre{}", if count >  1 { "s" } else { "" }),
+            Locale::De => format!("{count} Mitglied{}", if count == 1 { "" } else { "er" }),
+            Locale::Ja => format!("{count}人")
+        }
+    }
+
+    // a member's current and longest streaks of puzzles completed on their release day
+    pub fn streak(self, current : usize, longest : usize) -> String
+    {
+        match self
+        {
+            Locale::En => format!("streak {current}, best {longest}"),
+            Locale::Fr => format!("série {current}, record {longest}"),
+            Locale::De => format!("Serie {current}, Bestwert {longest}"),
+            Locale::Ja => format!("連続{current}日、最長{longest}日")
+        }
+    }
+
+    pub fn first_solves(self, count : usize) -> String
+    {
+        match self
+        {
+            Locale::En => format!("{count} first solve{}", if count == 1 { "" } else { "s" }),
+            Locale::Fr => format!("{count} première{} résolution{}", if count > 1 { "s" } else { "" }, if count > 1 { "s" } else { "" }),
+            Locale::De => format!("{count}× Erster"),
+            Locale::Ja => format!("一番乗り{count}回")
+        }
+    }
+
+    // describe a duration using its largest whole unit
+    pub fn duration(self, dur : Duration) -> String
+    {
+        let (count, unit) = match dur.num_minutes()
+        {
+            m if m % (60 * 24) == 0 => (m / (60 * 24), 0),
+            m if m % 60        == 0 => (m / 60,        1),
+            m                       => (m,             2)
+        };
+
+        let one = count == 1;
+        match (self, unit)
+        {
+            (Locale::En, 0) => format!("{count} day{}",    if one { "" } else { "s" }),
+            (Locale::En, 1) => format!("{count} hour{}",   if one { "" } else { "s" }),
+            (Locale::En, _) => format!("{count} minute{}", if one { "" } else { "s" }),
+            (Locale::Fr, 0) => format!("{count} jour{}",   if one { "" } else { "s" }),
+            (Locale::Fr, 1) => format!("{count} heure{}",  if one { "" } else { "s" }),
+            (Locale::Fr, _) => format!("{count} minute{}", if one { "" } else { "s" }),
+            (Locale::De, 0) => format!("{count} {}",       if one { "Tag"    } else { "Tagen"    }),
+            (Locale::De, 1) => format!("{count} {}",       if one { "Stunde" } else { "Stunden"  }),
+            (Locale::De, _) => format!("{count} {}",       if one { "Minute" } else { "Minuten"  }),
+            (Locale::Ja, 0) => format!("{count}日"),
+            (Locale::Ja, 1) => format!("{count}時間"),
+            (Locale::Ja, _) => format!("{count}分")
+        }
+    }
+
+    // decimal numbers with a fixed number of decimal places, using a decimal comma where appropriate
+    pub fn decimal(self, value : f64, places : usize) -> String
+    {
+        let formatted = format!("{value:.places$}");
+        match self
+        {
+            Locale::Fr | Locale::De => formatted.replace('.', ","),
+            Locale::En | Locale::Ja => formatted
+        }
+    }
+
+    // names joined as a list, with this locale's word for "and" before the last name
+    // Japanese templates follow each name with an honorific, so the list does the same between names
+    pub fn list(self, names : &[&str]) -> String
+    {
+        let (separator, last) = match self
+        {
+            Locale::En => (", ",       " and "),
+            Locale::Fr => (", ",       " et "),
+            Locale::De => (", ",       " und "),
+            Locale::Ja => (" さん、", " さんと ")
+        };
+        match names
+        {
+            []               => String::new(),
+            [name]           => name.to_string(),
+            [init @ .., end] => format!("{}{last}{end}", init.join(separator))
+        }
+    }
+
+    pub fn achievement(self, achievement : Achievement) -> &'static str
+    {
+        match (self, achievement)
+        {
+            (Locale::En, Achievement::FirstGold) => "First Gold Star",
+            (Locale::En, Achievement::AllStars)  => "All Fifty Stars",
+            (Locale::En, Achievement::Lightning) => "Lightning Streak",
+            (Locale::En, Achievement::NightOwl)  => "Night Owl",
+            (Locale::En, Achievement::CatchUp)   => "Catching Up",
+            (Locale::Fr, Achievement::FirstGold) => "Première étoile d'or",
+            (Locale::Fr, Achievement::AllStars)  => "Cinquante étoiles",
+            (Locale::Fr, Achievement::Lightning) => "Série éclair",
+            (Locale::Fr, Achievement::NightOwl)  => "Oiseau de nuit",
+            (Locale::Fr, Achievement::CatchUp)   => "Rattrapage",
+            (Locale::De, Achievement::FirstGold) => "Erster Goldstern",
+            (Locale::De, Achievement::AllStars)  => "Alle fünfzig Sterne",
+            (Locale::De, Achievement::Lightning) => "Blitzserie",
+            (Locale::De, Achievement::NightOwl)  => "Nachteule",
+            (Locale::De, Achievement::CatchUp)   => "Aufholjagd",
+            (Locale::Ja, Achievement::FirstGold) => "初めての金の星",
+            (Locale::Ja, Achievement::AllStars)  => "50個の星",
+            (Locale::Ja, Achievement::Lightning) => "電光石火",
+            (Locale::Ja, Achievement::NightOwl)  => "夜更かし",
+            (Locale::Ja, Achievement::CatchUp)   => "追い上げ"
+        }
+    }
+
+    // names of tie-breakers, as used in footnotes to the standings
+    pub fn tie_breaker(self, rule : TieBreaker) -> &'static str
+    {
+        match (self, rule)
+        {
+            (Locale::En, TieBreaker::Stars)     => "most stars",
+            (Locale::En, TieBreaker::FinalStar) => "earliest final star",
+            (Locale::En, TieBreaker::SolveTime) => "least total solve time",
+            (Locale::En, TieBreaker::GoldStars) => "most gold stars",
+            (Locale::Fr, TieBreaker::Stars)     => "le plus d'étoiles",
+            (Locale::Fr, TieBreaker::FinalStar) => "la dernière étoile la plus tôt",
+            (Locale::Fr, TieBreaker::SolveTime) => "le temps de résolution total le plus court",
+            (Locale::Fr, TieBreaker::GoldStars) => "le plus d'étoiles d'or",
+            (Locale::De, TieBreaker::Stars)     => "meiste Sterne",
+            (Locale::De, TieBreaker::FinalStar) => "frühester letzter Stern",
+            (Locale::De, TieBreaker::SolveTime) => "kürzeste Gesamtlösungszeit",
+            (Locale::De, TieBreaker::GoldStars) => "meiste Goldsterne",
+            (Locale::Ja, TieBreaker::Stars)     => "星の数",
+            (Locale::Ja, TieBreaker::FinalStar) => "最後の星の早さ",
+            (Locale::Ja, TieBreaker::SolveTime) => "合計解答時間",
+            (Locale::Ja, TieBreaker::GoldStars) => "金の星の数"
+        }
+    }
+
+    // positions in standings
+    pub fn ordinal(self, pos : usize) -> String
+    {
+        match self
+        {
+            Locale::En            => format!("{pos})"),
+            Locale::Fr if pos == 1 => "1er".to_string(),
+            Locale::Fr            => format!("{pos}e"),
+            Locale::De            => format!("{pos}."),
+            Locale::Ja            => format!("{pos}位")
+        }
+    }
+
+    // timestamps, with day and month names in this locale's language
+    pub fn timestamp<Tz : TimeZone>(self, ts : &DateTime<Tz>) -> String where Tz::Offset : std::fmt::Display
+    {
+        let (format, locale) = match self
+        {
+            Locale::En => ("%Y-%m-%d %H:%M:%S %Z",            chrono::Locale::en_GB),
+            Locale::Fr => ("%A %-d %B %Y à %H:%M:%S %Z",      chrono::Locale::fr_FR),
+            Locale::De => ("%A, %-d. %B %Y, %H:%M:%S %Z",     chrono::Locale::de_DE),
+            Locale::Ja => ("%Y年%-m月%-d日(%a) %H:%M:%S %Z", chrono::Locale::ja_JP)
+        };
+        ts.format_localized(format, locale).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn durations_use_their_largest_whole_unit()
+    {
+        assert_eq!(Locale::En.duration(Duration::days(1)),     "1 day");
+        assert_eq!(Locale::En.duration(Duration::hours(36)),   "36 hours");
+        assert_eq!(Locale::En.duration(Duration::minutes(90)), "90 minutes");
+        assert_eq!(Locale::Ja.duration(Duration::hours(2)),    "2時間");
+    }
+
+    #[test]
+    fn french_treats_zero_and_one_as_singular()
+    {
+        assert_eq!(Locale::Fr.stars(0), "0 étoile");
+        assert_eq!(Locale::Fr.stars(1), "1 étoile");
+        assert_eq!(Locale::Fr.stars(2), "2 étoiles");
+        assert_eq!(Locale::En.stars(0), "0 stars");
+    }
+
+    #[test]
+    fn lists_join_the_last_name_with_and()
+    {
+        assert_eq!(Locale::En.list(&["Alice"]),                 "Alice");
+        assert_eq!(Locale::En.list(&["Alice", "Bob"]),          "Alice and Bob");
+        assert_eq!(Locale::Fr.list(&["Alice", "Bob", "Carol"]), "Alice, Bob et Carol");
+        assert_eq!(Locale::Ja.list(&["Alice", "Bob", "Carol"]), "Alice さん、Bob さんと Carol");
+    }
+
+    #[test]
+    fn decimals_use_a_decimal_comma_where_appropriate()
+    {
+        assert_eq!(Locale::En.decimal(2.5, 2), "2.50");
+        assert_eq!(Locale::De.decimal(2.5, 2), "2,50");
+        assert_eq!(Locale::Fr.decimal(2.0, 1), "2,0");
+    }
+}
@@ -1,18 +1,47 @@
-use std::{ fs::File, io::Read, path::PathBuf };
+use std::{ fs::File, io::Read, path::PathBuf, collections::BTreeMap, sync::mpsc::{ self, RecvTimeoutError, TryRecvError }};
 use chrono::{ Utc, DateTime, Datelike, TimeDelta };
 use reqwest::blocking::Client;
+use signal_hook::{ consts::{ SIGINT, SIGTERM, SIGHUP }, iterator::Signals };
 
 mod error;
 use error::{ FestiveError, FestiveResult };
 
 mod env;
-use env::{ Var, Args };
+use env::Args;
+
+mod config;
+use config::{ Config, Target };
+
+mod cache;
 
 mod event;
 use event::Event;
 
+mod export;
+use export::Export;
+
+mod html;
+mod image;
+mod chart;
+
+mod members;
+use members::Change;
+
+mod achievements;
+use achievements::Achievement;
+
+mod streaks;
+use streaks::Streak;
+
+mod schedule;
+
+mod locale;
+
+mod template;
+use template::{ Message, Templates };
+
 mod webhook;
-use webhook::Webhook;
+use webhook::{ Route, Outbox, Files };
 
 fn main()
 {
@@ -25,9 +54,11 @@ fn main()
 
 fn initialise() -> FestiveResult<()>
 {
-    // mandatory environment variables
-    let leaderboard = Var::Leaderboard.get()?;
-    let session     = Var::Session.get()?;
+    // built-in templates are checked before anything is rendered from them
+    Templates::load_built_in()?;
+
+    // mandatory configuration from environment variables or the configuration file
+    let mut config = Config::load()?;
 
     // parse command-line arguments
     let args = Args::parse();
@@ -37,34 +68,36 @@ fn initialise() -> FestiveResult<()>
                                   .build().map_err(|_| FestiveError::Init)?;
 
     // initiate the main loop
-    let result = notify_cycle(&leaderboard, &session, &args, &client);
+    let result = notify_cycle(&mut config, &args, &client);
     if let Err(e) = &result
     {
         // attempt to send status message about fatal error
         // ignore these results, as the program is already exiting
-        let _ = Webhook::send("⚠ Festive Bot experienced an unrecoverable error, exiting!", &[], Webhook::Status, &client);
-        let _ = Webhook::send(&format!("⚠ Error: {e:?}"),                                   &[], Webhook::Status, &client);
+        // these are sent regardless of quiet hours
+        for webhook in config.route(Route::Errors)
+        {
+            let _ = webhook.send(&webhook.target.render(Message::Fatal, &[]),                            Vec::new(), Route::Errors.key(), &config, &client);
+            let _ = webhook.send(&webhook.target.render(Message::Error, &[("error", &format!("{e:?}"))]), Vec::new(), Route::Errors.key(), &config, &client);
+        }
     }
     result
 }
 
-fn notify_cycle(leaderboard : &str, session : &str, args : &Args, client : &Client) -> FestiveResult<()>
+fn notify_cycle(config : &mut Config, args : &Args, client : &Client) -> FestiveResult<()>
 {
+    // messages are held back from targets during their quiet hours
+    let mut outbox = Outbox::default();
+
     // status message notifying about initilisation
     println!("initialising");
-    Webhook::send(&format!("🦀 Festive Bot v{} is initialising...", env!("CARGO_PKG_VERSION")), &[], Webhook::Status, client)?;
+    outbox.send(Route::Status, |t| Ok(t.render(Message::Initialising, &[("version", &env!("CARGO_PKG_VERSION"))])), config, client)?;
 
-    // set handler for POSIX termination signals
-    // hander needs to own the HTTP client it uses, so give it a clone
+    // set handler for POSIX signals, forwarding them to the main loop
+    // SIGINT and SIGTERM terminate Festive Bot, SIGHUP reloads the configuration
     println!("setting handler for SIGINT, SIGTERM, and SIGHUP signals");
-    let handler_client = client.clone();
-    ctrlc::set_handler(move ||
-    {
-        println!("received termination signal, exiting...");
-        let _ = Webhook::send("🦀 Received termination signal, exiting!", &[], Webhook::Status, &handler_client);
-        std::process::exit(0);
-    })
-    .map_err(|_| FestiveError::Init)?;
+    let mut signals        = Signals::new([SIGINT, SIGTERM, SIGHUP]).map_err(|_| FestiveError::Init)?;
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || for signal in signals.forever() { if sender.send(signal).is_err() { break } });
 
     // populate currently-live AoC years
     println!("determining currently-live AoC years");
@@ -76,36 +109,112 @@ fn notify_cycle(leaderboard : &str, session : &str, args : &Args, client : &Clie
     prev = Event::trunc_ts(&prev, args.period)?;
 
     // reusable buffers for efficiency
-    let mut events = Vec::new();
+    let mut events  = Vec::new();
+    let mut members = BTreeMap::new();
     let mut buffer = String::new();
+    let mut reload = false;
+    let mut idle   = false;
 
     println!("initialisation successful");
-    Webhook::send("🦀 Initialisation successful!",
-                  &[("params.txt", format!("leaderboard: {leaderboard}\n\
-                                            all years:   {}\n\
-                                            period:      {}\n\
-                                            standings:   {}\n\
-                                            heartbeat    {:?}\n\
-                                            live years:  {live:?}\n",
-                                            args.all_years,
-                                            args.period.num_minutes(),
-                                            args.standings.num_minutes(),
-                                            args.heartbeat.map(|d| d.num_minutes())).as_bytes())],
-                  Webhook::Status, client)?;
+    let params = format!("leaderboard: {}\n\
+                         all years:   {}\n\
+                         digest:      {}\n\
+                         period:      {}\n\
+                         standings:   {}\n\
+                         teams:       {}\n\
+                         heartbeat    {:?}\n\
+                         refresh:     {}\n\
+                         backoff:     {:?}\n\
+                         busy:        {}\n\
+                         countdown:   {:?}\n\
+                         live years:  {live:?}\n",
+                         config.leaderboard,
+                         args.all_years,
+                         args.digest,
+                         args.period.num_minutes(),
+                         args.standings.num_minutes(),
+                         args.teams.num_minutes(),
+                         args.heartbeat.map(|d| d.num_minutes()),
+                         args.refresh.num_minutes(),
+                         args.backoff.map(|d| d.num_minutes()),
+                         args.busy.num_minutes(),
+                         args.countdown.iter().map(|d| d.num_minutes()).collect::<Vec<_>>());
+    outbox.attach(Route::Status, |t| Ok((t.render(Message::Initialised, &[]), vec![("params.txt".to_string(), params.clone().into_bytes())])),
+                  config, client)?;
 
     loop
     {
+        // status message when the Advent of Code season ends, or on initialisation outside the season
+        let was_idle = std::mem::replace(&mut idle, schedule::idle(&prev, args)?);
+        if idle && !was_idle
+        {
+            let until = schedule::season_start(prev.year(), args)?;
+            outbox.send(Route::Status, |t| Ok(t.render(Message::Idle, &[("until", &t.timestamp(&until))])), config, client)?;
+        }
+
         // attempt to sleep until next iteration
-        let current = prev + args.period;
+        let current = schedule::next_iteration(&prev, outbox.until(config), args)?;
         year        = current.year();
         println!("attempting to sleep until {current}");
-        match (current - Utc::now()).to_std()
+        if (current - Utc::now()).to_std().is_err() { println!("not sleeping, a previous iteration overran") }
+
+        // wait for signals while sleeping, including those received during the previous iteration
+        // when a previous iteration overran there's no sleeping, but signals received during it are still handled
+        loop
         {
-            Ok(duration) => { std::thread::sleep(duration); println!("woke at {}", Utc::now()) },
-            Err(_)       => println!("not sleeping, a previous iteration overran")
+            let signal = match (current - Utc::now()).to_std()
+            {
+                Ok(duration) => receiver.recv_timeout(duration),
+                Err(_)       => receiver.try_recv().map_err(|e| match e
+                {
+                    TryRecvError::Empty        => RecvTimeoutError::Timeout,
+                    TryRecvError::Disconnected => RecvTimeoutError::Disconnected
+                })
+            };
+            match signal
+            {
+                Ok(SIGHUP) =>
+                {
+                    println!("received SIGHUP, reloading configuration next iteration");
+                    reload = true;
+                },
+                Ok(_) =>
+                {
+                    println!("received termination signal, exiting...");
+                    let _ = outbox.send(Route::Status, |t| Ok(t.render(Message::Terminating, &[])), config, client);
+                    let _ = outbox.flush(true, config, client);
+                    return Ok(())
+                },
+                Err(RecvTimeoutError::Timeout)      => { println!("woke at {}", Utc::now()); break },
+                Err(RecvTimeoutError::Disconnected) => return Err(FestiveError::Init)
+            }
         }
         println!();
 
+        // reload configuration if requested by SIGHUP or any of its files have changed
+        // the previous configuration is kept if reloading fails
+        if std::mem::take(&mut reload) || config.changed()
+        {
+            println!("reloading configuration");
+            match Config::load()
+            {
+                Ok(reloaded) =>
+                {
+                    *config = reloaded;
+                    outbox.send(Route::Status, |t| Ok(t.render(Message::Reloaded, &[])), config, client)?;
+                },
+                Err(e) =>
+                {
+                    println!("failed to reload configuration: {e}");
+                    config.mark_unchanged();
+                    outbox.send(Route::Errors, |t| Ok(t.render(Message::ReloadFailed, &[("error", &e)])), config, client)?;
+                }
+            }
+        }
+
+        // deliver messages held during quiet hours which have since ended
+        outbox.flush(false, config, client)?;
+
         // if a timestamp has occurred since the previous iteration, it can trigger something to happen this iteration
         let trigger = |ts| prev < ts && ts <= current;
 
@@ -115,7 +224,18 @@ fn notify_cycle(leaderboard : &str, session : &str, args : &Args, client : &Clie
             let heartbeat_ts = Event::trunc_ts(&current, heartbeat_dur)?;
             if trigger(heartbeat_ts)
             {
-                Webhook::send(&format!("🦀 Heartbeat {heartbeat_ts}"), &[], Webhook::Status, client)?;
+                outbox.send(Route::Heartbeat, |t| Ok(t.render(Message::Heartbeat, &[("timestamp", &t.timestamp(&heartbeat_ts))])), config, client)?;
+            }
+        }
+
+        // countdown announcements before the next first puzzle unlock, which is next year's once this year's puzzles have all unlocked
+        let season = schedule::countdown_year(&current)?;
+        let first  = Event::puzzle_unlock(season, 1)?;
+        for &countdown in &args.countdown
+        {
+            if trigger(first - countdown)
+            {
+                outbox.send(Route::Announcements, |t| Ok(t.render(Message::Countdown, &[("year", &season), ("countdown", &t.locale.duration(countdown))])), config, client)?;
             }
         }
 
@@ -123,21 +243,23 @@ fn notify_cycle(leaderboard : &str, session : &str, args : &Args, client : &Clie
         if trigger(Event::puzzle_unlock(year, 1)?) && *live.end() != year
         {
             live = 2015 ..= year;
-            Webhook::send(&format!("🦀 Adding {year} to live years!"), &[], Webhook::Status, client)?;
+            outbox.send(Route::Status, |t| Ok(t.render(Message::AddingYear, &[("year", &year)])), config, client)?;
         }
 
         // only report on past years when all_years is set
         for request_year in live.clone().filter(|&y| args.all_years || y == year)
         {
             // send AoC API request, parsing the response to a vector of events
+            // the current year is requested as often as allowed, past years only once per refresh interval
             println!("sending AoC API request for year {request_year}");
-            let response = Event::request(request_year, leaderboard, session, client)?;
+            let refresh  = if request_year == year { TimeDelta::zero() } else { args.refresh };
+            let response = Event::request(request_year, &config.leaderboard, &config.session, refresh, client)?;
             println!("parsing response");
-            Event::parse(&response, &mut events)?;
+            Event::parse(&response, &config.members, &mut events, &mut members)?;
             println!("parsed {} events", events.len());
 
             // read RFC 3339 timestamp from filesystem, defaulting to 28 days before current iteration
-            let timestamp_path = PathBuf::from(format!("timestamp_{request_year}_{leaderboard}"));
+            let timestamp_path = PathBuf::from(format!("timestamp_{request_year}_{}", config.leaderboard));
             println!("reading {}", timestamp_path.display());
             let timestamp = File::open(&timestamp_path).ok().and_then(|mut f|
             {
@@ -154,11 +276,74 @@ fn notify_cycle(leaderboard : &str, session : &str, args : &Args, client : &Clie
             println!("obtained timestamp {timestamp}");
 
             // message for each puzzle event that took place after the latest timestamp, up to the start of this iteration
-            for e in events.iter().skip_while(|e| e.timestamp() <= &timestamp).take_while(|e| e.timestamp() < &current)
+            // or, when digest is set, a single message for all of them, per target, containing the parts routed to that target
+            let new_events = || events.iter().skip_while(|e| e.timestamp() <= &timestamp).take_while(|e| e.timestamp() < &current);
+            if args.digest
             {
-                Webhook::send(&e.fmt()?, &[], Webhook::Notify, client)?;
-                println!("updating timestamp to {}", e.timestamp());
-                std::fs::write(&timestamp_path, e.timestamp().to_rfc3339()).map_err(|_| FestiveError::File)?;
+                if let Some(last) = new_events().last()
+                {
+                    for (webhook, route, digest) in Event::digests(request_year, &new_events().collect::<Vec<_>>(), &events, config)?
+                    {
+                        outbox.send_to(webhook, route, digest, Vec::new(), config, client)?;
+                    }
+                    println!("updating timestamp to {}", last.timestamp());
+                    std::fs::write(&timestamp_path, last.timestamp().to_rfc3339()).map_err(|_| FestiveError::File)?;
+                }
+            }
+            else
+            {
+                for e in new_events()
+                {
+                    outbox.send(Route::part(e.star()), |t| e.fmt(&events, t, config.mentions), config, client)?;
+                    println!("updating timestamp to {}", e.timestamp());
+                    std::fs::write(&timestamp_path, e.timestamp().to_rfc3339()).map_err(|_| FestiveError::File)?;
+                }
+            }
+
+            // announce changes to the leaderboard's members, only for the most recent live year
+            // there's nothing to compare against the first time the members are seen
+            // the snapshot includes ignored members, but changes to them aren't announced
+            if request_year == *live.end()
+            {
+                if let Some(snapshot) = members::read_snapshot(request_year, &config.leaderboard)
+                {
+                    for change in Change::diff(&snapshot, &members).into_iter().filter(|c| !config.members.get(&c.member()).is_some_and(|m| m.ignore))
+                    {
+                        outbox.send(Route::Members, |t| Ok(change.fmt(request_year, &events, &config.members, t)), config, client)?;
+                    }
+                }
+                members::write_snapshot(request_year, &config.leaderboard, &members)?;
+            }
+
+            // announce achievements as they're earned, only for the most recent live year
+            // achievements earned before they're first recorded aren't announced
+            if request_year == *live.end()
+            {
+                let earned = Achievement::earned(request_year, &events, config.targets["notify"].timezone)?;
+                let record = achievements::read(request_year, &config.leaderboard);
+                if let Some(record) = &record
+                {
+                    for (member, achievements) in &earned
+                    {
+                        let Some(name) = events.iter().find(|e| e.member() == *member).map(|e| e.name(config.mentions)) else { continue };
+                        for achievement in achievements.iter().filter(|a| !record.get(member).is_some_and(|r| r.contains(a)))
+                        {
+                            outbox.send(Route::Achievements, |t| Ok(achievement.fmt(request_year, &name, t)), config, client)?;
+                        }
+                    }
+                }
+
+                // achievements stay recorded once announced, so they aren't announced again if they stop being earned for a while
+                // such as when a member leaves and rejoins, is ignored and then unignored, or the time zone for night-time changes
+                let mut record = record.unwrap_or_default();
+                for (member, achievements) in earned { record.entry(member).or_default().extend(achievements) }
+                achievements::write(request_year, &config.leaderboard, &record)?;
+            }
+
+            // write the standings to disk, when an output directory is configured
+            if let Some(output) = &config.output
+            {
+                Export::write(&output.formats, &output.directory, request_year, &config.leaderboard, &events, &current, &config.targets["notify"])?;
             }
 
             // announcements made only during December
@@ -171,24 +356,70 @@ fn notify_cycle(leaderboard : &str, session : &str, args : &Args, client : &Clie
                     // new AoC year announcement
                     if day == 1
                     {
-                        Webhook::send(&format!("🎄 [{year}] Advent of Code is now live! 🎉"), &[], Webhook::Notify, client)?
+                        outbox.send(Route::Unlock, |t| Ok(t.render(Message::Live, &[("year", &year)])), config, client)?
                     }
 
                     // new puzzle announcement
-                    Webhook::send(&format!("🎄 [{year}] Puzzle {day:02} is now unlocked! 🔓"), &[], Webhook::Notify, client)?;
+                    outbox.send(Route::Unlock, |t| Ok(t.render(Message::Unlock, &[("year", &year), ("day", &format!("{day:02}"))])), config, client)?;
+                }
+
+                // results of each puzzle, announced a fixed time after it unlocked
+                for results_day in 1 ..= 25
+                {
+                    if trigger(Event::puzzle_unlock(year, results_day)? + args.results)
+                    {
+                        outbox.send(Route::Results, |t| Event::results(year, results_day, &events, t), config, client)?;
+                    }
+                }
+
+                // notable streaks, announced when extended by new completions, and when broken by the release day of a puzzle ending before it's completed
+                let on_time = Streak::on_time(&events)?;
+                let name    = |member| events.iter().find(|e| e.member() == member).map(|e| e.name(config.mentions));
+                for e in new_events().filter(|e| e.star() == 2 && on_time.get(&e.member()).is_some_and(|days| days.contains(&e.day())))
+                {
+                    let days = Streak::run(&on_time[&e.member()], e.day());
+                    if days >= streaks::NOTABLE { outbox.send(Route::Streaks, |t| Ok(Streak::extended(year, &e.name(config.mentions), days, t)), config, client)? }
+                }
+                for streak_day in 1 ..= 25
+                {
+                    if !trigger(Event::puzzle_unlock(year, streak_day)? + TimeDelta::days(1)) { continue }
+                    for (&member, days) in on_time.iter().filter(|(_, days)| !days.contains(&streak_day))
+                    {
+                        let days = Streak::run(days, streak_day - 1);
+                        let Some(name) = name(member).filter(|_| days >= streaks::NOTABLE) else { continue };
+                        outbox.send(Route::Streaks, |t| Ok(Streak::broken(year, &name, days, t)), config, client)?;
+                    }
                 }
 
                 // leaderboard standings announcement
                 if trigger(Event::trunc_ts(&current, args.standings)?) && current - timestamp < TimeDelta::days(1)
                 {
-                    let standings = if events.is_empty() { "No scores yet: get programming!\n".to_string() } else { Event::standings(&events)? };
-                    Webhook::send(&format!("🎄 [{year}] Current Standings 🏆"), &[(&format!("standings_{year}_12_{day:02}.txt"), standings.as_bytes())], Webhook::Notify, client)?;
+                    outbox.attach(Route::Standings, |t| standings(t.render(Message::Standings, &[("year", &year)]), &format!("{year}_12_{day:02}"), year, &events, &current, t), config, client)?;
                 }
 
-                // sign off for the year
-                if (current + args.period).year() != request_year
+                // team standings announcement, only when any teams are configured
+                if trigger(Event::trunc_ts(&current, args.teams)?) && current - timestamp < TimeDelta::days(1) && config.members.values().any(|m| m.team.is_some())
                 {
-                    Webhook::send(&format!("🎄 [{year}] Festive Bot signing off. Happy New Year! 👋"), &[], Webhook::Notify, client)?;
+                    outbox.attach(Route::Standings, |t|
+                    {
+                        let standings = Event::team_standings(&events, &config.members, &members, config.aggregate, t.locale)?;
+                        let standings = if standings.is_empty() { t.render(Message::NoTeamScores, &[]) + "\n" } else { standings };
+                        Ok((t.render(Message::TeamStandings, &[("year", &year)]), vec![(format!("team_standings_{year}_12_{day:02}.txt"), standings.into_bytes())]))
+                    },
+                    config, client)?;
+                }
+
+                // sign off for the year, with a recap of the year's achievements and final standings
+                if schedule::next_iteration(&current, None, args)?.year() != request_year
+                {
+                    let earned = Achievement::earned(year, &events, config.targets["notify"].timezone)?;
+                    outbox.attach(Route::Announcements, |t|
+                    {
+                        let content = t.render(Message::SignOff, &[("year", &year)]);
+                        let content = if earned.is_empty() { content } else { format!("{content}\n{}", Achievement::recap(year, &earned, &events, t)) };
+                        standings(content, &format!("{year}_final"), year, &events, &current, t)
+                    },
+                    config, client)?;
                 }
             }
         }
@@ -198,3 +429,16 @@ fn notify_cycle(leaderboard : &str, session : &str, args : &Args, client : &Clie
         println!("completed iteration at {}", Utc::now());
     }
 }
+
+// the standings following the content of a message, if the target has an inline layout, and attached in each of its formats
+// inline standings are truncated to fit in the rest of a single message
+fn standings(mut content : String, suffix : &str, year : i32, events : &[Event], current : &DateTime<Utc>, target : &Target) -> FestiveResult<(String, Files)>
+{
+    if let Some(inline) = target.inline
+    {
+        let limit = target.format.limit().map(|limit| limit.saturating_sub(content.encode_utf16().count() + 1));
+        content   = format!("{content}\n{}", inline.render(events, current, target, limit)?);
+    }
+    let files = target.standings.iter().map(|export| Ok((format!("standings_{suffix}.{}", export.extension()), export.render(year, events, current, target)?)));
+    Ok((content, files.collect::<FestiveResult<_>>()?))
+}
@@ -0,0 +1,122 @@
+use std::{ collections::BTreeMap, path::PathBuf };
+use json::JsonValue;
+use crate::{ config::{ Target, Member }, event::Event, template::Message, error::{ FestiveResult, FestiveError }};
+
+// changes to the members of a leaderboard between two snapshots, with their numeric IDs and names from the AoC API
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change
+{
+    Join(u64, String),
+    Leave(u64, String),
+    Rename(u64, String, String)
+}
+
+impl Change
+{
+    // compare snapshots of the members of a leaderboard, keyed by their numeric IDs
+    pub fn diff(old : &BTreeMap<u64, String>, new : &BTreeMap<u64, String>) -> Vec<Change>
+    {
+        let mut changes = Vec::new();
+        for (&id, name) in new
+        {
+            match old.get(&id)
+            {
+                None                       => changes.push(Change::Join(id, name.clone())),
+                Some(prev) if prev != name => changes.push(Change::Rename(id, prev.clone(), name.clone())),
+                Some(_)                    => ()
+            }
+        }
+        for (&id, name) in old
+        {
+            if !new.contains_key(&id) { changes.push(Change::Leave(id, name.clone())) }
+        }
+        changes
+    }
+
+    pub fn member(&self) -> u64
+    {
+        match self { Change::Join(id, _) | Change::Leave(id, _) | Change::Rename(id, ..) => *id }
+    }
+
+    // newcomers are welcomed with their existing star count
+    // members joining or leaving are named by their aliases, if configured, but renames are always between names from the AoC API
+    pub fn fmt(&self, year : i32, events : &[Event], config : &BTreeMap<u64, Member>, target : &Target) -> String
+    {
+        let alias = |id : &u64, name : &String| config.get(id).and_then(|m| m.alias.clone()).unwrap_or_else(|| name.clone());
+        match self
+        {
+            Change::Join(id, name) =>
+            {
+                let stars = events.iter().filter(|e| e.member() == *id).count();
+                target.render(Message::Join, &[("year", &year), ("name", &alias(id, name)), ("stars", &target.locale.stars(stars))])
+            },
+            Change::Leave(id, name)     => target.render(Message::Leave,  &[("year", &year), ("name", &alias(id, name))]),
+            Change::Rename(_, old, new) => target.render(Message::Rename, &[("year", &year), ("old",  old), ("new", new)])
+        }
+    }
+}
+
+// per-year, per-leaderboard snapshot of members' names, cached to the filesystem
+fn snapshot_path(year : i32, leaderboard : &str) -> PathBuf
+{
+    PathBuf::from(format!("members_{year}_{leaderboard}.json"))
+}
+
+// a missing or malformed snapshot is treated as no previous snapshot
+pub fn read_snapshot(year : i32, leaderboard : &str) -> Option<BTreeMap<u64, String>>
+{
+    let contents = std::fs::read_to_string(snapshot_path(year, leaderboard)).ok()?;
+    json::parse(&contents).ok()?
+        .entries()
+        .map(|(id, name)| Some((id.parse().ok()?, name.as_str()?.to_string())))
+        .collect()
+}
+
+pub fn write_snapshot(year : i32, leaderboard : &str, members : &BTreeMap<u64, String>) -> FestiveResult<()>
+{
+    let mut json = JsonValue::new_object();
+    for (id, name) in members { json[id.to_string()] = name.as_str().into() }
+    std::fs::write(snapshot_path(year, leaderboard), json.dump()).map_err(|_| FestiveError::File)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::event::tests::{ at, events };
+
+    fn snapshot(members : &[(u64, &str)]) -> BTreeMap<u64, String>
+    {
+        members.iter().map(|&(id, name)| (id, name.to_string())).collect()
+    }
+
+    #[test]
+    fn joins_and_leaves_are_detected()
+    {
+        let old = snapshot(&[(1, "Alice"), (2, "Bob")]);
+        let new = snapshot(&[(1, "Alice"), (3, "Carol")]);
+        assert_eq!(Change::diff(&old, &new), [Change::Join(3, "Carol".to_string()), Change::Leave(2, "Bob".to_string())]);
+        assert!(Change::diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn renames_are_detected_by_numeric_id()
+    {
+        // a member taking another's former name is a rename, not a join or leave
+        let old = snapshot(&[(1, "Alice"), (2, "Bob")]);
+        let new = snapshot(&[(1, "Bob"), (2, "Robert")]);
+        assert_eq!(Change::diff(&old, &new), [Change::Rename(1, "Alice".to_string(), "Bob".to_string()),
+                                              Change::Rename(2, "Bob".to_string(), "Robert".to_string())]);
+    }
+
+    #[test]
+    fn newcomers_are_welcomed_with_their_star_count()
+    {
+        let events = events(&[(3, Some("Carol"), &[(1, 1, at(1, 60)), (1, 2, at(1, 120))])]);
+        let target = Target::from_json("{}");
+        assert_eq!(Change::Join(3, "Carol".to_string()).fmt(2025, &events, &BTreeMap::new(), &target),
+                   ":wave: [2025] Welcome to the leaderboard, Carol! They have 2 stars so far. :star:");
+        assert_eq!(Change::Rename(1, "Alice".to_string(), "Alicia".to_string()).fmt(2025, &events, &BTreeMap::new(), &target),
+                   ":label: [2025] Alice is now known as Alicia.");
+    }
+}
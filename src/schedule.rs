@@ -0,0 +1,182 @@
+use chrono::{ DateTime, Utc, Datelike };
+use crate::{ env::Args, event::Event, error::FestiveResult };
+
+// outside the Advent of Code season, there's nothing to report on unless past years are being reported on
+// the season begins one iteration period before the first puzzle unlock, and ends at the end of December
+pub fn idle(ts : &DateTime<Utc>, args : &Args) -> FestiveResult<bool>
+{
+    Ok(!args.all_years && ts.month() != 12 && *ts < season_start(ts.year(), args)?)
+}
+
+pub fn season_start(year : i32, args : &Args) -> FestiveResult<DateTime<Utc>>
+{
+    Ok(Event::puzzle_unlock(year, 1)? - args.period)
+}
+
+// countdowns are to the next first puzzle unlock, which is in the following year once this year's last puzzle has unlocked
+pub fn countdown_year(ts : &DateTime<Utc>) -> FestiveResult<i32>
+{
+    Ok(if *ts < Event::puzzle_unlock(ts.year(), 25)? { ts.year() } else { ts.year() + 1 })
+}
+
+// determine when the iteration following the one beginning at prev should begin
+// with a fixed schedule, iterations are evenly spaced by the iteration period
+// with an adaptive schedule, the iteration period is only used while busy after each puzzle unlock, otherwise backing off
+// when idle, the next iteration begins at the start of the next season
+// in all cases, iterations always begin at puzzle unlocks, results and standings announcements, heartbeats, and countdowns
+// held messages are delivered at the first iteration period boundary after quiet hours end, if given
+pub fn next_iteration(prev : &DateTime<Utc>, quiet_until : Option<DateTime<Utc>>, args : &Args) -> FestiveResult<DateTime<Utc>>
+{
+    // the next puzzle unlock, possibly in the following year
+    let year   = prev.year();
+    let first  = Event::puzzle_unlock(year, 1)?;
+    let unlock = match (prev.month(), prev.day())
+    {
+        (12, day) if day <= 25 =>
+        {
+            let today = Event::puzzle_unlock(year, day)?;
+            if      *prev < today { today                                }
+            else if day   < 25    { Event::puzzle_unlock(year,     day+1)? }
+            else                  { Event::puzzle_unlock(year + 1, 1)?     }
+        },
+        (12, _) => Event::puzzle_unlock(year + 1, 1)?,
+        _       => first
+    };
+
+    let mut next = match args.backoff
+    {
+        _ if idle(prev, args)? => season_start(year, args)?,
+        None                   => *prev + args.period,
+        Some(backoff)          =>
+        {
+            // busy for a while after each puzzle unlock
+            let busy = prev.month() == 12 && prev.day() <= 25 &&
+            {
+                let today = Event::puzzle_unlock(year, prev.day())?;
+                today <= *prev && *prev < today + args.busy
+            };
+
+            // outside December, only past years can have any activity
+            // when they're not being reported on, there's nothing to do until the next puzzle unlock
+            match if busy { Some(args.period) } else if prev.month() == 12 || args.all_years { Some(backoff) } else { None }
+            {
+                Some(step) => Event::trunc_ts(&(*prev + step), step)?,
+                None       => unlock
+            }
+        }
+    };
+
+    // don't sleep past any timestamps which trigger announcements
+    next = next.min(unlock);
+    if prev.month() == 12                   { next = next.min(Event::trunc_ts(prev, args.standings)? + args.standings) }
+    if prev.month() == 12                   { next = next.min(Event::trunc_ts(prev, args.teams)?     + args.teams)     }
+    if let Some(heartbeat) = args.heartbeat { next = next.min(Event::trunc_ts(prev, heartbeat)?      + heartbeat)      }
+
+    // results are announced a fixed time after each puzzle unlocks
+    if prev.month() == 12
+    {
+        for day in 1 ..= 25
+        {
+            let results = Event::puzzle_unlock(year, day)? + args.results;
+            if *prev < results { next = next.min(results); break }
+        }
+    }
+    let season = Event::puzzle_unlock(countdown_year(prev)?, 1)?;
+    for countdown in args.countdown.iter().map(|&c| season - c).filter(|c| prev < c)
+    {
+        next = next.min(countdown)
+    }
+    if let Some(until) = quiet_until
+    {
+        let boundary = Event::trunc_ts(&until, args.period)?;
+        next = next.min(if boundary < until { boundary + args.period } else { boundary })
+    }
+    Ok(next)
+}
+
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use chrono::{ Duration, TimeZone };
+
+    fn args(period : i64, backoff : Option<i64>, countdown : &[i64]) -> Args
+    {
+        Args
+        {
+            all_years: false,
+            digest:    false,
+            period:    Duration::minutes(period),
+            standings: Duration::days(1),
+            teams:     Duration::days(1),
+            results:   Duration::days(1),
+            heartbeat: None,
+            refresh:   Duration::days(1),
+            backoff:   backoff.map(Duration::minutes),
+            busy:      Duration::hours(4),
+            countdown: countdown.iter().map(|&c| Duration::minutes(c)).collect()
+        }
+    }
+
+    fn utc(mo : u32, d : u32, h : u32, mi : u32) -> DateTime<Utc>
+    {
+        Utc.with_ymd_and_hms(2025, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn fixed_schedule_uses_the_iteration_period()
+    {
+        let args = args(60, None, &[]);
+        assert_eq!(next_iteration(&utc(12, 1, 7, 0),  None, &args).unwrap(), utc(12, 1, 8, 0));
+        assert_eq!(next_iteration(&utc(12, 1, 4, 30), None, &args).unwrap(), utc(12, 1, 5, 0));
+    }
+
+    #[test]
+    fn adaptive_schedule_backs_off_until_the_next_unlock()
+    {
+        let args = args(15, Some(60), &[]);
+        assert_eq!(next_iteration(&utc(12, 1, 5, 15), None, &args).unwrap(), utc(12, 1, 5, 30));
+        assert_eq!(next_iteration(&utc(12, 1, 9, 0),  None, &args).unwrap(), utc(12, 1, 10, 0));
+        assert_eq!(next_iteration(&utc(12, 2, 4, 30), None, &args).unwrap(), utc(12, 2, 5, 0));
+    }
+
+    #[test]
+    fn idle_until_the_season_starts()
+    {
+        let args = args(60, None, &[]);
+        assert!( idle(&utc(6, 15, 12, 0),  &args).unwrap());
+        assert!( idle(&utc(11, 30, 23, 0), &args).unwrap());
+        assert!(!idle(&utc(12, 1, 4, 0),   &args).unwrap());
+        assert!(!idle(&utc(6, 15, 12, 0),  &Args { all_years: true, ..args }).unwrap());
+    }
+
+    #[test]
+    fn idle_iterations_wake_for_countdowns_and_the_season_start()
+    {
+        let season = args(60, None, &[]);
+        assert_eq!(next_iteration(&utc(6, 15, 12, 0), None, &season).unwrap(), utc(12, 1, 4, 0));
+
+        let countdown = args(60, None, &[1440, 60]);
+        assert_eq!(next_iteration(&utc(6, 15, 12, 0),  None, &countdown).unwrap(), utc(11, 30, 5, 0));
+        assert_eq!(next_iteration(&utc(11, 30, 5, 0),  None, &countdown).unwrap(), utc(12, 1, 4, 0));
+    }
+
+    #[test]
+    fn countdowns_after_the_last_puzzle_are_to_the_following_year()
+    {
+        // 340 days less 8 hours before the 1st of December 2026 is 13:00 on the 26th of December 2025
+        let args = args(15, Some(1440), &[340 * 1440 - 480]);
+        assert_eq!(countdown_year(&utc(12, 24, 12, 0)).unwrap(), 2025);
+        assert_eq!(countdown_year(&utc(12, 25, 5, 0)).unwrap(),  2026);
+        assert_eq!(next_iteration(&utc(12, 26, 12, 0), None, &args).unwrap(), utc(12, 26, 13, 0));
+    }
+
+    #[test]
+    fn held_messages_are_delivered_at_the_boundary_after_quiet_hours()
+    {
+        let args = args(60, None, &[]);
+        assert_eq!(next_iteration(&utc(6, 15, 12, 0), Some(utc(6, 16, 6, 30)), &args).unwrap(), utc(6, 16, 7, 0));
+        assert_eq!(next_iteration(&utc(6, 15, 12, 0), Some(utc(6, 16, 6, 0)),  &args).unwrap(), utc(6, 16, 6, 0));
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::{ BTreeMap, BTreeSet };
+use chrono::{ DateTime, Utc, Duration };
+use crate::{ config::Target, event::Event, template::Message, error::FestiveResult };
+
+// streaks of at least this many days are announced when extended or broken
+pub const NOTABLE : usize = 3;
+
+// a member's runs of consecutive puzzles completed on their release day, within a day of unlocking
+// the current streak isn't broken until the release day of the next puzzle ends without it being completed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Streak
+{
+    pub current: usize,
+    pub longest: usize
+}
+
+impl Streak
+{
+    // days on which each member completed both parts of the puzzle on its release day, keyed by their numeric IDs
+    pub fn on_time(events : &[Event]) -> FestiveResult<BTreeMap<u64, BTreeSet<u32>>>
+    {
+        let mut days : BTreeMap<u64, BTreeSet<u32>> = BTreeMap::new();
+        for e in events.iter().filter(|e| e.star() == 2)
+        {
+            if *e.timestamp() < Event::puzzle_unlock(e.year(), e.day())? + Duration::days(1) { days.entry(e.member()).or_default().insert(e.day()); }
+        }
+        Ok(days)
+    }
+
+    // length of the run of consecutive days ending on a day
+    pub fn run(days : &BTreeSet<u32>, end : u32) -> usize
+    {
+        (1 ..= end).rev().take_while(|day| days.contains(day)).count()
+    }
+
+    // each member's streaks at a point in time during a year, keyed by their numeric IDs
+    pub fn of(year : i32, events : &[Event], now : &DateTime<Utc>) -> FestiveResult<BTreeMap<u64, Streak>>
+    {
+        // the latest puzzle whose release day has ended
+        let mut closed = 0;
+        for day in 1 ..= 25
+        {
+            if Event::puzzle_unlock(year, day)? + Duration::days(1) <= *now { closed = day }
+        }
+
+        Ok(Self::on_time(events)?.into_iter().map(|(member, days)|
+        {
+            let end     = if days.contains(&(closed + 1)) { closed + 1 } else { closed };
+            let longest = days.iter().map(|&day| Self::run(&days, day)).max().unwrap_or(0);
+            (member, Streak { current: Self::run(&days, end), longest })
+        }).collect())
+    }
+
+    pub fn extended(year : i32, name : &str, days : usize, target : &Target) -> String
+    {
+        target.render(Message::StreakExtended, &[("year", &year), ("name", &name), ("days", &days)])
+    }
+
+    pub fn broken(year : i32, name : &str, days : usize, target : &Target) -> String
+    {
+        target.render(Message::StreakBroken, &[("year", &year), ("name", &name), ("days", &days)])
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::event::tests::{ at, events };
+
+    // both stars of a puzzle, a number of hours after it unlocked
+    fn gold(day : u32, hours : i64) -> [(u32, u8, i64) ; 2]
+    {
+        [(day, 1, at(day, 3600 * hours - 60)), (day, 2, at(day, 3600 * hours))]
+    }
+
+    fn streak(events : &[Event], now : i64) -> Streak
+    {
+        Streak::of(2025, events, &DateTime::from_timestamp(now, 0).unwrap()).unwrap()[&1]
+    }
+
+    #[test]
+    fn only_release_day_completions_of_both_stars_count()
+    {
+        let completions = [gold(1, 1), gold(2, 23), gold(3, 25), [(4, 1, at(4, 60)), (4, 2, at(4, 120))]].concat();
+        let events      = events(&[(1, Some("Alice"), &completions[.. 7])]);
+        assert_eq!(Streak::on_time(&events).unwrap()[&1], BTreeSet::from([1, 2]));
+    }
+
+    #[test]
+    fn current_streak_lasts_until_the_release_day_ends()
+    {
+        let completions = [gold(1, 1), gold(2, 1), gold(3, 1), gold(5, 1)].concat();
+        let before      = events(&[(1, Some("Alice"), &completions[.. 6])]);
+        let after       = events(&[(1, Some("Alice"), &completions)]);
+
+        // puzzle 4 is still on its release day, so the streak isn't yet broken
+        assert_eq!(streak(&before, at(4, 3600)), Streak { current: 3, longest: 3 });
+
+        // puzzle 4's release day has ended without it being completed, and puzzle 5 is completed later
+        assert_eq!(streak(&before, at(5, 60)),   Streak { current: 0, longest: 3 });
+        assert_eq!(streak(&after,  at(5, 7200)), Streak { current: 1, longest: 3 });
+        assert_eq!(streak(&after,  at(7, 0)),    Streak { current: 0, longest: 3 });
+    }
+
+    #[test]
+    fn runs_end_on_a_given_day()
+    {
+        let days = BTreeSet::from([1, 2, 3, 5, 6]);
+        assert_eq!(Streak::run(&days, 3), 3);
+        assert_eq!(Streak::run(&days, 4), 0);
+        assert_eq!(Streak::run(&days, 6), 2);
+        assert_eq!(Streak::run(&days, 0), 0);
+    }
+}
@@ -0,0 +1,329 @@
+use std::{ collections::HashMap, sync::OnceLock };
+use json::JsonValue;
+use crate::{ locale::Locale, error::{ FestiveResult, FestiveError }};
+
+// every kind of message that Festive Bot sends, each rendered from a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Message
+{
+    Initialising,
+    Initialised,
+    Terminating,
+    Fatal,
+    Error,
+    Reloaded,
+    ReloadFailed,
+    Idle,
+    Heartbeat,
+    AddingYear,
+    Countdown,
+    Completion,
+    DigestHeader,
+    DigestLine,
+    DigestEntry,
+    Join,
+    Leave,
+    Rename,
+    Live,
+    Unlock,
+    Standings,
+    TeamStandings,
+    NoScores,
+    NoTeamScores,
+    SignOff,
+    Held,
+    Others,
+    TieBroken,
+    TieShared,
+    Results,
+    ResultsGold,
+    ResultsSilver,
+    NoResults,
+    Achievement,
+    AchievementsRecap,
+    AchievementsLine,
+    StreakExtended,
+    StreakBroken,
+    FirstCompletion,
+    DigestFirst
+}
+
+impl Message
+{
+    // iterate through all kinds of message
+    fn iter() -> impl Iterator<Item = Message>
+    {
+        [Message::Initialising, Message::Initialised, Message::Terminating, Message::Fatal, Message::Error,
+         Message::Reloaded, Message::ReloadFailed, Message::Idle, Message::Heartbeat, Message::AddingYear,
+         Message::Countdown, Message::Completion, Message::DigestHeader, Message::DigestLine, Message::DigestEntry,
+         Message::Join, Message::Leave, Message::Rename, Message::Live, Message::Unlock,
+         Message::Standings, Message::TeamStandings, Message::NoScores, Message::NoTeamScores, Message::SignOff,
+         Message::Held, Message::Others, Message::TieBroken, Message::TieShared,
+         Message::Results, Message::ResultsGold, Message::ResultsSilver, Message::NoResults,
+         Message::Achievement, Message::AchievementsRecap, Message::AchievementsLine,
+         Message::StreakExtended, Message::StreakBroken, Message::FirstCompletion, Message::DigestFirst].into_iter()
+    }
+
+    // key of this message's template in the configuration file
+    pub fn key(self) -> &'static str
+    {
+        match self
+        {
+            Message::Initialising      => "initialising",
+            Message::Initialised       => "initialised",
+            Message::Terminating       => "terminating",
+            Message::Fatal             => "fatal",
+            Message::Error             => "error",
+            Message::Reloaded          => "reloaded",
+            Message::ReloadFailed      => "reload_failed",
+            Message::Idle              => "idle",
+            Message::Heartbeat         => "heartbeat",
+            Message::AddingYear        => "adding_year",
+            Message::Countdown         => "countdown",
+            Message::Completion        => "completion",
+            Message::DigestHeader      => "digest_header",
+            Message::DigestLine        => "digest_line",
+            Message::DigestEntry       => "digest_entry",
+            Message::Join              => "join",
+            Message::Leave             => "leave",
+            Message::Rename            => "rename",
+            Message::Live              => "live",
+            Message::Unlock            => "unlock",
+            Message::Standings         => "standings",
+            Message::TeamStandings     => "team_standings",
+            Message::NoScores          => "no_scores",
+            Message::NoTeamScores      => "no_team_scores",
+            Message::SignOff           => "sign_off",
+            Message::Held              => "held",
+            Message::Others            => "others",
+            Message::TieBroken         => "tie_broken",
+            Message::TieShared         => "tie_shared",
+            Message::Results           => "results",
+            Message::ResultsGold       => "results_gold",
+            Message::ResultsSilver     => "results_silver",
+            Message::NoResults         => "no_results",
+            Message::Achievement       => "achievement",
+            Message::AchievementsRecap => "achievements_recap",
+            Message::AchievementsLine  => "achievements_line",
+            Message::StreakExtended    => "streak_extended",
+            Message::StreakBroken      => "streak_broken",
+            Message::FirstCompletion   => "first_completion",
+            Message::DigestFirst       => "digest_first"
+        }
+    }
+
+    // the named placeholders which may be used in this message's template
+    fn placeholders(self) -> &'static [&'static str]
+    {
+        match self
+        {
+            Message::Initialising                                              => &["version"],
+            Message::Error | Message::ReloadFailed                             => &["error"],
+            Message::Idle                                                      => &["until"],
+            Message::Heartbeat                                                 => &["timestamp"],
+            Message::AddingYear | Message::Live | Message::DigestHeader |
+            Message::Standings  | Message::TeamStandings | Message::SignOff    => &["year"],
+            Message::Countdown                                                 => &["year", "countdown"],
+            Message::Completion  | Message::FirstCompletion                    => &["year", "name", "day", "part", "emoji", "score", "points", "elapsed"],
+            Message::DigestLine                                                => &["year", "day", "part", "emoji", "entries"],
+            Message::DigestEntry | Message::DigestFirst                        => &["name", "score", "points", "elapsed"],
+            Message::Join                                                      => &["year", "name", "stars"],
+            Message::Leave                                                     => &["year", "name"],
+            Message::Rename                                                    => &["year", "old", "new"],
+            Message::Unlock      | Message::Results                            => &["year", "day"],
+            Message::ResultsGold                                               => &["position", "name", "elapsed", "delta"],
+            Message::ResultsSilver                                             => &["names"],
+            Message::Achievement                                               => &["year", "name", "achievement"],
+            Message::AchievementsRecap                                         => &["year"],
+            Message::AchievementsLine                                          => &["name", "achievements"],
+            Message::StreakExtended | Message::StreakBroken                    => &["year", "name", "days"],
+            Message::Others                                                    => &["count"],
+            Message::TieBroken                                                 => &["higher", "lower", "score", "rule"],
+            Message::TieShared                                                 => &["names", "position", "score"],
+            Message::Initialised | Message::Terminating | Message::Fatal |
+            Message::Reloaded    | Message::NoScores    | Message::NoTeamScores |
+            Message::Held        | Message::NoResults                          => &[]
+        }
+    }
+}
+
+// a template's text split into literal text and named placeholders
+enum Segment
+{
+    Text(String),
+    Placeholder(String)
+}
+
+// templates for each kind of message, where configured, otherwise the locale's built-in templates are used
+pub struct Templates(HashMap<Message, Vec<Segment>>);
+
+// the built-in templates of every locale, parsed once at startup
+static BUILT_IN : OnceLock<HashMap<(Locale, Message), Vec<Segment>>> = OnceLock::new();
+
+impl Templates
+{
+    // parse the built-in templates of every locale, failing if any of them are invalid
+    // this must be done before any message is rendered
+    pub fn load_built_in() -> FestiveResult<()>
+    {
+        if BUILT_IN.get().is_some() { return Ok(()) }
+
+        let mut built_in = HashMap::new();
+        for locale in Locale::iter()
+        {
+            for message in Message::iter()
+            {
+                built_in.insert((locale, message), Self::segments(message, locale.template(message)).map_err(|_| FestiveError::Init)?);
+            }
+        }
+        let _ = BUILT_IN.set(built_in);
+        Ok(())
+    }
+
+    // read and validate templates from the configuration file
+    pub fn parse(json : &JsonValue, key : &str) -> FestiveResult<Templates>
+    {
+        if !json.is_null() && !json.is_object()
+        {
+            println!("configuration key {key:?} should be an object");
+            return Err(FestiveError::Parse)
+        }
+
+        let mut templates = HashMap::new();
+        for (key, value) in json.entries()
+        {
+            let Some(message) = Message::iter().find(|m| m.key() == key) else
+            {
+                println!("unknown template {key:?}, expected one of: {}", Message::iter().map(Message::key).collect::<Vec<_>>().join(", "));
+                return Err(FestiveError::Parse)
+            };
+            let Some(text) = value.as_str() else
+            {
+                println!("template {key:?} should be a string");
+                return Err(FestiveError::Parse)
+            };
+            templates.insert(message, Self::segments(message, text)?);
+        }
+        Ok(Templates(templates))
+    }
+
+    // templates from other take precedence over these
+    pub fn overlay(mut self, other : Templates) -> Templates
+    {
+        self.0.extend(other.0);
+        self
+    }
+
+    // split a template into segments, checking that its braces match and its placeholders are known
+    // literal braces are written by doubling them
+    fn segments(message : Message, text : &str) -> FestiveResult<Vec<Segment>>
+    {
+        let invalid = |reason : String|
+        {
+            println!("invalid template {:?}: {reason}", message.key());
+            FestiveError::Parse
+        };
+
+        let mut segments = Vec::new();
+        let mut literal  = String::new();
+        let mut chars    = text.chars().peekable();
+        while let Some(c) = chars.next()
+        {
+            match (c, chars.peek())
+            {
+                ('{', Some('{')) | ('}', Some('}')) => { literal.push(c); chars.next(); },
+                ('}', _)                            => return Err(invalid("unmatched '}'".to_string())),
+                ('{', _)                            =>
+                {
+                    let mut name = String::new();
+                    loop
+                    {
+                        match chars.next()
+                        {
+                            Some('}') => break,
+                            Some(c)   => name.push(c),
+                            None      => return Err(invalid("unmatched '{'".to_string()))
+                        }
+                    }
+                    if !message.placeholders().contains(&name.as_str())
+                    {
+                        return Err(invalid(format!("unknown placeholder {{{name}}}, expected one of: {}",
+                                                   message.placeholders().iter().map(|p| format!("{{{p}}}")).collect::<Vec<_>>().join(", "))))
+                    }
+                    segments.push(Segment::Text(std::mem::take(&mut literal)));
+                    segments.push(Segment::Placeholder(name));
+                },
+                _ => literal.push(c)
+            }
+        }
+        segments.push(Segment::Text(literal));
+        Ok(segments)
+    }
+
+    // render a message's template, substituting the values of its placeholders
+    pub fn render(&self, locale : Locale, message : Message, values : &[(&str, &dyn std::fmt::Display)]) -> String
+    {
+        let segments = match self.0.get(&message)
+        {
+            Some(segments) => segments,
+            None           => &BUILT_IN.get().expect("built-in templates are loaded at startup")[&(locale, message)]
+        };
+
+        segments.iter().map(|segment| match segment
+        {
+            Segment::Text(text)        => text.clone(),
+            Segment::Placeholder(name) => values.iter().find(|(key, _)| key == name).map(|(_, value)| value.to_string()).unwrap_or_default()
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn render(segments : &[Segment]) -> String
+    {
+        segments.iter().map(|segment| match segment { Segment::Text(text) => text.clone(), Segment::Placeholder(name) => format!("<{name}>") }).collect()
+    }
+
+    #[test]
+    fn built_in_templates_are_valid()
+    {
+        for locale in [Locale::En, Locale::Fr, Locale::De, Locale::Ja]
+        {
+            for message in Message::iter()
+            {
+                assert!(Templates::segments(message, locale.template(message)).is_ok(), "{locale:?} {message:?}");
+            }
+        }
+        assert!(Templates::load_built_in().is_ok());
+    }
+
+    #[test]
+    fn placeholders_and_escaped_braces()
+    {
+        assert_eq!(render(&Templates::segments(Message::Live, "{{{year}}} live").unwrap()), "{<year>} live");
+        assert_eq!(render(&Templates::segments(Message::Live, "").unwrap()), "");
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected()
+    {
+        for text in ["{year", "year}", "{day}", "{}", "{{year}"]
+        {
+            assert!(Templates::segments(Message::Live, text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn configured_templates_override_built_in_templates()
+    {
+        Templates::load_built_in().unwrap();
+        let templates = Templates::parse(&json::parse(r#"{ "live": "{year}!" }"#).unwrap(), "templates").unwrap();
+        assert_eq!(templates.render(Locale::En, Message::Live,       &[("year", &2025)]), "2025!");
+        assert_eq!(templates.render(Locale::En, Message::AddingYear, &[("year", &2025)]), "🦀 Adding 2025 to live years!");
+        assert!(Templates::parse(&json::parse(r#"{ "unknown": "" }"#).unwrap(), "templates").is_err());
+    }
+}
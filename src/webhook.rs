@@ -1,81 +1,457 @@
+use chrono::{ DateTime, Utc };
+use json::{ object, JsonValue };
 use reqwest::{ blocking::{ Client, multipart::{ Form, Part }}, StatusCode };
-use crate::{ env::Var, error::{ FestiveResult, FestiveError }};
+use crate::{ config::{ Config, Target }, template::Message, error::{ FestiveResult, FestiveError }};
 
-// handles for webhook URLs
-#[derive(Debug)]
-pub enum Webhook { Notify, Status }
+// a named target which messages are delivered to
+#[derive(Clone, Copy)]
+pub struct Webhook<'a>
+{
+    pub name:   &'a str,
+    pub target: &'a Target
+}
+
+// kinds of message, each routed to any number of targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Route
+{
+    PartOne,
+    PartTwo,
+    Unlock,
+    Standings,
+    Results,
+    Achievements,
+    Streaks,
+    Members,
+    Announcements,
+    Heartbeat,
+    Status,
+    Errors
+}
+
+// limits imposed by a webhook's service on the size of each message
+pub struct Limits
+{
+    pub content: usize,
+    pub file:    usize,
+    pub files:   usize
+}
+
+// Discord limits content to 2000 characters, and attachments to 10 files totalling 10 MiB
+pub const DISCORD : Limits = Limits { content: 2000, file: 10 * 1024 * 1024, files: 10 };
+
+// what to do with content exceeding a webhook's content limit
+#[derive(Clone, Copy)]
+pub enum Overflow { Split, Attach }
 
-impl Webhook
+// how messages are posted to a target's URL
+// Discord webhooks receive multi-part forms, while JSON feeds receive each message as a JSON object
+#[derive(Clone, Copy)]
+pub enum Format { Discord, Json }
+
+// files attached to a message, with their names
+pub type Files = Vec<(String, Vec<u8>)>;
+
+// messages held back from targets during their quiet hours, by target name, along with their routes
+#[derive(Default)]
+pub struct Outbox(Vec<(String, Route, String, Files)>);
+
+impl Route
 {
-    // attempt to get this webhook's URL
-    fn url(&self) -> FestiveResult<String>
+    pub fn iter() -> impl Iterator<Item = Route>
+    {
+        [Route::PartOne, Route::PartTwo, Route::Unlock, Route::Standings, Route::Results, Route::Achievements, Route::Streaks,
+         Route::Members, Route::Announcements, Route::Heartbeat, Route::Status, Route::Errors].into_iter()
+    }
+
+    // key of this route in the configuration file and JSON feeds
+    pub fn key(self) -> &'static str
     {
         match self
         {
-            Webhook::Notify => Var::Notify,
-            Webhook::Status => Var::Status
+            Route::PartOne       => "part_one",
+            Route::PartTwo       => "part_two",
+            Route::Unlock        => "unlock",
+            Route::Standings     => "standings",
+            Route::Results       => "results",
+            Route::Achievements  => "achievements",
+            Route::Streaks       => "streaks",
+            Route::Members       => "members",
+            Route::Announcements => "announcements",
+            Route::Heartbeat     => "heartbeat",
+            Route::Status        => "status",
+            Route::Errors        => "errors"
         }
-        .get()
     }
 
+    // the targets this route is sent to when not configured
+    pub fn default_targets(self) -> &'static [&'static str]
+    {
+        match self
+        {
+            Route::Heartbeat | Route::Status | Route::Errors => &["status"],
+            _                                                => &["notify"]
+        }
+    }
+
+    // puzzle completions are routed by which part was completed
+    pub fn part(star : u8) -> Route
+    {
+        if star == 1 { Route::PartOne } else { Route::PartTwo }
+    }
+}
+
+impl Format
+{
+    // the longest content that can be sent in a single message, if there is a limit
+    pub fn limit(self) -> Option<usize>
+    {
+        match self
+        {
+            Format::Discord => Some(DISCORD.content),
+            Format::Json    => None
+        }
+    }
+}
+
+impl Webhook<'_>
+{
     // written for Discord's webhook API
     // may work partially for other services, but only verified for Discord
-    pub fn send(content : &str, files : &[(&str, &[u8])], webhook : Webhook, client : &Client) -> FestiveResult<()>
+    // messages exceeding the webhook's limits are sent as multiple messages
+    pub fn send(self, content : &str, files : Files, route : &str, config : &Config, client : &Client) -> FestiveResult<()>
     {
-        println!("webhook content: {content:?}");
-        println!("webhook file count: {}", files.len());
+        println!("webhook {} content: {content:?}", self.name);
+        println!("webhook {} file count: {}", self.name, files.len());
 
-        // only send HTTP request if webhook variable set
-        match webhook.url().as_deref()
+        // only send HTTP request if webhook URL set
+        match (self.target.url.as_deref(), self.target.format)
         {
-            Err(_)  => println!("webhook {webhook:?} environment variable not present, not sending request"),
-            Ok(url) =>
+            (None, _)                 => println!("webhook {} URL not configured, not sending request", self.name),
+            (Some(url), Format::Json) =>
+            {
+                println!("webhook URL: {url}");
+                Self::post_json(url, route, content, &files, client)?
+            },
+            (Some(url), Format::Discord) =>
             {
                 println!("webhook URL: {url}");
 
-                loop
+                let messages = Self::fit(content, files, &DISCORD, config.overflow);
+                if messages.len() > 1 { println!("sending as {} messages to fit within limits", messages.len()) }
+                for (content, files) in messages
                 {
-                    // build multi-part form with text content and files
-                    let mut form = Form::new().text("content", content.to_string());
-                    for (ix, (name, data)) in files.iter().enumerate()
-                    {
-                        form = form.part(format!("files[{ix}]"), Part::bytes(data.to_vec()).file_name(name.to_string()));
-                    }
-
-                    // send the request
-                    let response = client.post(url)
-                                         .header("wait", "true")
-                                         .multipart(form)
-                                         .send()
-                                         .map_err(|_| FestiveError::Http)?;
-
-
-                    match response.status()
-                    {
-                        // expected status codes for successful request
-                        StatusCode::OK | StatusCode::NO_CONTENT => break,
-
-                        // keep retrying request until rate-limiting period ends
-                        StatusCode::TOO_MANY_REQUESTS =>
-                        {
-                            let retry_secs = json::parse(&response.text().map_err(|_| FestiveError::Http)?).map_err(|_| FestiveError::Parse)?["retry_after"].as_f32().unwrap_or(0.0);
-                            println!("rate-limited for {retry_secs}s");
-                            std::thread::sleep(std::time::Duration::from_millis((retry_secs * 1000.0) as u64));
-                        },
-
-                        // unexpected status code
-                        c =>
-                        {
-                            println!("unexpected status code: {c}");
-                            return Err(FestiveError::Http)
-                        }
-                    }
-
-                    println!("retrying");
+                    Self::post(url, &content, &files, client)?;
                 }
             }
         }
 
         Ok(())
     }
+
+    // split content into chunks no longer than the limit, on line boundaries where possible
+    // lines are kept verbatim, including blank lines, so chunks split on line boundaries can be rejoined with line breaks
+    // lengths are measured in UTF-16 code units, as Discord does
+    fn split(content : &str, limit : usize) -> Vec<String>
+    {
+        let mut chunks                 = Vec::new();
+        let mut chunk : Option<String> = None;
+        let mut len                    = 0;
+        for line in content.split('\n')
+        {
+            // continue the current chunk if this line fits in it, otherwise start a new chunk
+            match chunk.as_mut()
+            {
+                Some(chunk) if len + 1 + line.encode_utf16().count() <= limit => { chunk.push('\n'); len += 1 },
+                Some(_)                                                       => { chunks.extend(chunk.take()); len = 0 },
+                None                                                          => ()
+            }
+            let chunk = chunk.get_or_insert_with(String::new);
+
+            // lines which are too long by themselves are split on character boundaries
+            for c in line.chars()
+            {
+                if len + c.len_utf16() > limit { chunks.push(std::mem::take(chunk)); len = 0 }
+                chunk.push(c);
+                len += c.len_utf16();
+            }
+        }
+        chunks.extend(chunk);
+        chunks
+    }
+
+    // arrange content and files into messages which fit within the limits
+    // content is either split into multiple messages, or its overflow moved into an attachment
+    // files too large for a single message are left out, as parts of them couldn't be opened, and files are spread over as many messages as needed
+    fn fit(content : &str, mut files : Files, limits : &Limits, overflow : Overflow) -> Vec<(String, Files)>
+    {
+        // content within the limit is left untouched
+        let mut chunks = match overflow
+        {
+            _ if content.encode_utf16().count() <= limits.content => vec![content.to_string()],
+            Overflow::Split                                       => Self::split(content, limits.content),
+            Overflow::Attach                                      =>
+            {
+                let notice    = "\n(continued in attachment)";
+                let mut split = Self::split(content, limits.content - notice.encode_utf16().count()).into_iter();
+                let first     = split.next().unwrap_or_default();
+                let rest      = content.strip_prefix(&first).map_or_else(|| split.collect::<Vec<_>>().join("\n"), |rest| rest.strip_prefix('\n').unwrap_or(rest).to_string());
+                files.insert(0, ("overflow.txt".to_string(), rest.into_bytes()));
+                vec![first + notice]
+            }
+        };
+
+        // leave out files too large for a single message
+        let mut parts = Vec::new();
+        for (name, data) in files
+        {
+            if data.len() <= limits.file { parts.push((name, data)); continue }
+            println!("attachment {name} is too large to send ({} bytes), leaving it out", data.len());
+        }
+
+        // files are attached to the first message, overflowing into further messages without content
+        let mut messages = Vec::new();
+        let mut current  = (chunks.remove(0), Vec::new());
+        let mut size     = 0;
+        for (name, data) in parts
+        {
+            if current.1.len() == limits.files || size + data.len() > limits.file
+            {
+                messages.push(std::mem::take(&mut current));
+                size = 0;
+            }
+            size += data.len();
+            current.1.push((name, data));
+        }
+        messages.push(current);
+
+        // remaining content chunks follow the first message
+        let rest = messages.split_off(1);
+        messages.extend(chunks.into_iter().map(|c| (c, Vec::new())));
+        messages.extend(rest);
+        messages
+    }
+
+    // send a single message, retrying while rate-limited
+    fn post(url : &str, content : &str, files : &Files, client : &Client) -> FestiveResult<()>
+    {
+        loop
+        {
+            // build multi-part form with text content and files
+            let mut form = Form::new().text("content", content.to_string());
+            for (ix, (name, data)) in files.iter().enumerate()
+            {
+                form = form.part(format!("files[{ix}]"), Part::bytes(data.clone()).file_name(name.clone()));
+            }
+
+            // send the request
+            let response = client.post(url)
+                                 .header("wait", "true")
+                                 .multipart(form)
+                                 .send()
+                                 .map_err(|_| FestiveError::Http)?;
+
+
+            match response.status()
+            {
+                // expected status codes for successful request
+                StatusCode::OK | StatusCode::NO_CONTENT => return Ok(()),
+
+                // keep retrying request until rate-limiting period ends
+                StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    let retry_secs = json::parse(&response.text().map_err(|_| FestiveError::Http)?).map_err(|_| FestiveError::Parse)?["retry_after"].as_f32().unwrap_or(0.0);
+                    println!("rate-limited for {retry_secs}s");
+                    std::thread::sleep(std::time::Duration::from_millis((retry_secs * 1000.0) as u64));
+                },
+
+                // unexpected status code
+                c =>
+                {
+                    println!("unexpected status code: {c}");
+                    return Err(FestiveError::Http)
+                }
+            }
+
+            println!("retrying");
+        }
+    }
+
+    // post a message to a JSON feed as a single object, containing its route, content, and any UTF-8 encoded files
+    fn post_json(url : &str, route : &str, content : &str, files : &Files, client : &Client) -> FestiveResult<()>
+    {
+        let mut json = object!{ route: route, content: content, files: JsonValue::new_array() };
+        for (name, data) in files
+        {
+            match std::str::from_utf8(data)
+            {
+                Ok(text) => json["files"].push(object!{ name: name.as_str(), content: text }).map_err(|_| FestiveError::Conv)?,
+                Err(_)   => println!("file {name} is not UTF-8 encoded, omitting it from JSON feed")
+            }
+        }
+
+        let response = client.post(url)
+                             .header("content-type", "application/json")
+                             .body(json.dump())
+                             .send()
+                             .map_err(|_| FestiveError::Http)?;
+
+        match response.status()
+        {
+            c if c.is_success() => Ok(()),
+            c                   =>
+            {
+                println!("unexpected status code: {c}");
+                Err(FestiveError::Http)
+            }
+        }
+    }
+}
+
+impl Outbox
+{
+    // send a message to each target on a route, rendered for that target
+    pub fn send(&mut self, route : Route, render : impl Fn(&Target) -> FestiveResult<String>, config : &Config, client : &Client) -> FestiveResult<()>
+    {
+        self.attach(route, |target| Ok((render(target)?, Vec::new())), config, client)
+    }
+
+    // send a message with attached files to each target on a route, both rendered for that target
+    pub fn attach(&mut self, route : Route, render : impl Fn(&Target) -> FestiveResult<(String, Files)>, config : &Config, client : &Client) -> FestiveResult<()>
+    {
+        for webhook in config.route(route)
+        {
+            let (content, files) = render(webhook.target)?;
+            self.send_to(webhook, route, content, files, config, client)?;
+        }
+        Ok(())
+    }
+
+    // send a message to a single target, unless it's in quiet hours, in which case it's held until they end
+    // unlock announcements are sent regardless when the target exempts them
+    pub fn send_to(&mut self, webhook : Webhook, route : Route, content : String, files : Files, config : &Config, client : &Client) -> FestiveResult<()>
+    {
+        match webhook.target.quiet_until(&Utc::now())
+        {
+            Some(until) if !(route == Route::Unlock && webhook.target.quiet.as_ref().is_some_and(|q| q.exempt_unlocks)) =>
+            {
+                println!("webhook {} is in quiet hours until {until}, holding message: {content:?}", webhook.name);
+                self.0.push((webhook.name.to_string(), route, content, files));
+                Ok(())
+            },
+            _ => webhook.send(&content, files, route.key(), config, client)
+        }
+    }
+
+    // the earliest time that quiet hours end for any target with held messages
+    pub fn until(&self, config : &Config) -> Option<DateTime<Utc>>
+    {
+        let now = Utc::now();
+        self.0.iter().filter_map(|(name, ..)| config.targets.get(name)?.quiet_until(&now)).min()
+    }
+
+    // deliver each target's held messages together in a single message, once its quiet hours have ended
+    // JSON feeds instead receive each held message separately, so that they keep their routes
+    // when forced, held messages are delivered regardless
+    // messages held for targets removed by reloading the configuration are dropped
+    pub fn flush(&mut self, force : bool, config : &Config, client : &Client) -> FestiveResult<()>
+    {
+        let now = Utc::now();
+        self.0.retain(|(name, ..)| config.targets.contains_key(name));
+        for (name, target) in &config.targets
+        {
+            if !force && target.quiet_until(&now).is_some() { continue }
+
+            let (held, rest) : (Vec<_>, Vec<_>) = std::mem::take(&mut self.0).into_iter().partition(|(n, ..)| n == name);
+            self.0 = rest;
+            if held.is_empty() { continue }
+
+            println!("delivering {} held messages to webhook {name}", held.len());
+            let webhook = Webhook { name, target };
+            if let Format::Json = target.format
+            {
+                for (_, route, content, files) in held { webhook.send(&content, files, route.key(), config, client)? }
+                continue
+            }
+
+            let mut content = target.render(Message::Held, &[]);
+            let mut files   = Vec::new();
+            for (_, _, held_content, held_files) in held
+            {
+                content.push('\n');
+                content.push_str(&held_content);
+                files.extend(held_files);
+            }
+            webhook.send(&content, files, "held", config, client)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    const LIMITS : Limits = Limits { content: 10, file: 4, files: 2 };
+
+    fn file(name : &str, size : usize) -> (String, Vec<u8>)
+    {
+        (name.to_string(), vec![0 ; size])
+    }
+
+    #[test]
+    fn split_keeps_blank_lines()
+    {
+        let content = "a\n\nb\n\n\nc";
+        let chunks  = Webhook::split(content, 3);
+        assert_eq!(chunks, ["a\n", "b\n\n", "c"]);
+        assert_eq!(chunks.join("\n"), content);
+
+        // code blocks split across chunks keep their leading lines and indentation
+        let content = "```\n\n  1st\n  2nd\n```";
+        assert_eq!(Webhook::split(content, 8).join("\n"), content);
+        assert!(Webhook::split(content, 8).iter().all(|c| c.encode_utf16().count() <= 8));
+    }
+
+    #[test]
+    fn split_long_lines_on_character_boundaries()
+    {
+        assert_eq!(Webhook::split("abcdefg\nhi", 3), ["abc", "def", "g", "hi"]);
+
+        // lengths are measured in UTF-16 code units
+        assert_eq!(Webhook::split("🦀🦀🦀", 4), ["🦀🦀", "🦀"]);
+    }
+
+    #[test]
+    fn fit_leaves_short_content_untouched()
+    {
+        assert_eq!(Webhook::fit("short\n\n", Vec::new(), &LIMITS, Overflow::Split), [("short\n\n".to_string(), Vec::new())]);
+    }
+
+    #[test]
+    fn fit_leaves_out_files_too_large_to_send()
+    {
+        let messages = Webhook::fit("content", vec![file("big.png", 5), file("a.txt", 2), file("b.txt", 2), file("c.txt", 1)], &LIMITS, Overflow::Split);
+        assert_eq!(messages, [("content".to_string(), vec![file("a.txt", 2), file("b.txt", 2)]), (String::new(), vec![file("c.txt", 1)])]);
+    }
+
+    #[test]
+    fn fit_moves_overflow_into_an_attachment()
+    {
+        let rest     = format!("{}\n\n  c", "b".repeat(40));
+        let messages = Webhook::fit(&format!("aa\n{rest}"), Vec::new(), &Limits { content: 30, file: 100, files: 10 }, Overflow::Attach);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].0, "aa\n(continued in attachment)");
+        assert_eq!(messages[0].1, [("overflow.txt".to_string(), rest.into_bytes())]);
+    }
+
+    #[test]
+    fn fit_splits_content_before_remaining_files()
+    {
+        let messages = Webhook::fit("aaaa\nbbbb\ncccc", vec![file("a", 1), file("b", 1), file("c", 1)], &LIMITS, Overflow::Split);
+        assert_eq!(messages, [("aaaa\nbbbb".to_string(), vec![file("a", 1), file("b", 1)]),
+                              ("cccc".to_string(),       Vec::new()),
+                              (String::new(),            vec![file("c", 1)])]);
+    }
 }